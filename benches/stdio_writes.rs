@@ -0,0 +1,107 @@
+//! Compares writing every frame to a server's stdin on its own with the batched writes
+//! [`StdioTransport`] does, for sequential and concurrent senders.
+//!
+//! Run with `cargo bench --bench stdio_writes`. On Linux the write syscalls per message are
+//! reported alongside the throughput.
+
+use futures::future::join_all;
+use mcp_client_rs::{StdioTransport, Transport};
+use std::collections::HashMap;
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+use tokio::sync::Mutex;
+
+const MESSAGES: usize = 20_000;
+const MESSAGE: &str = r#"{"jsonrpc":"2.0","id":1,"method":"tools/call","params":{"name":"echo","arguments":{"text":"hello"}}}"#;
+
+#[tokio::main]
+async fn main() {
+    println!("{MESSAGES} messages of {} bytes each\n", MESSAGE.len() + 1);
+
+    for concurrent in [false, true] {
+        let mode = if concurrent {
+            "concurrent"
+        } else {
+            "sequential"
+        };
+        report(&format!("per-frame, {mode}"), per_frame(concurrent).await);
+        report(&format!("batched, {mode}"), batched(concurrent).await);
+    }
+}
+
+// Each frame written and flushed under a lock, as the transport did before batching
+async fn per_frame(concurrent: bool) -> (Duration, Option<u64>) {
+    let mut child = Command::new("sh")
+        .args(["-c", "cat >/dev/null"])
+        .stdin(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let stdin = Arc::new(Mutex::new(child.stdin.take().unwrap()));
+    let send = |stdin: Arc<Mutex<tokio::process::ChildStdin>>| async move {
+        let mut frame = MESSAGE.as_bytes().to_vec();
+        frame.push(b'\n');
+        let mut stdin = stdin.lock().await;
+        stdin.write_all(&frame).await.unwrap();
+        stdin.flush().await.unwrap();
+    };
+
+    let measured = measure(concurrent, || send(stdin.clone())).await;
+    drop(stdin);
+    child.wait().await.unwrap();
+    measured
+}
+
+async fn batched(concurrent: bool) -> (Duration, Option<u64>) {
+    let transport =
+        StdioTransport::spawn("sh", vec!["-c", "cat >/dev/null"], HashMap::new()).unwrap();
+    let measured = measure(concurrent, || async {
+        transport.send(MESSAGE).await.unwrap();
+    })
+    .await;
+    transport.close().await.unwrap();
+    measured
+}
+
+// Time `MESSAGES` sends, awaiting each in turn or all at once
+async fn measure<F, Fut>(concurrent: bool, send: F) -> (Duration, Option<u64>)
+where
+    F: Fn() -> Fut,
+    Fut: std::future::Future<Output = ()>,
+{
+    let writes = write_syscalls();
+    let started = Instant::now();
+    if concurrent {
+        join_all((0..MESSAGES).map(|_| send())).await;
+    } else {
+        for _ in 0..MESSAGES {
+            send().await;
+        }
+    }
+    let elapsed = started.elapsed();
+    let writes = write_syscalls()
+        .zip(writes)
+        .map(|(after, before)| after - before);
+    (elapsed, writes)
+}
+
+fn report(name: &str, (elapsed, writes): (Duration, Option<u64>)) {
+    let per_second = MESSAGES as f64 / elapsed.as_secs_f64();
+    let writes = match writes {
+        Some(writes) => format!("{:.3} writes/message", writes as f64 / MESSAGES as f64),
+        None => "writes not measured".to_string(),
+    };
+    println!("{name:<24} {elapsed:>10.1?} {per_second:>10.0} messages/s  {writes}");
+}
+
+// Write syscalls made by this process so far, where the platform reports them
+fn write_syscalls() -> Option<u64> {
+    let io = std::fs::read_to_string("/proc/self/io").ok()?;
+    io.lines()
+        .find_map(|line| line.strip_prefix("syscw: "))?
+        .trim()
+        .parse()
+        .ok()
+}
@@ -1,5 +1,5 @@
 use dotenv::dotenv;
-use mcp_client_rs::{ClientError, Protocol};
+use mcp_client_rs::{ClientError, Protocol, DEFAULT_PROTOCOL_VERSION};
 use std::collections::HashMap;
 
 #[tokio::main]
@@ -13,7 +13,7 @@ async fn main() -> Result<(), ClientError> {
     );
 
     let client = Protocol::new(
-        "example-client",
+        DEFAULT_PROTOCOL_VERSION,
         "npx",
         vec!["-y", "@modelcontextprotocol/server-github"],
         envs,
@@ -0,0 +1,90 @@
+use crate::types::{
+    CallToolResponse, ClientError, ListToolsResponse, Prompt, ResourcesListResponse,
+    ResourcesReadResponse,
+};
+use crate::Protocol;
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+/// A [`Protocol`] handle whose calls share one absolute deadline, created with
+/// [`Protocol::with_deadline`].
+///
+/// # Example
+/// ```no_run
+/// # async fn run(client: &mcp_client_rs::Protocol) -> Result<(), mcp_client_rs::ClientError> {
+/// use std::time::{Duration, Instant};
+///
+/// let scoped = client.with_deadline(Instant::now() + Duration::from_secs(5));
+/// let tools = scoped.list_tools().await?;
+/// for tool in tools.tools {
+///     scoped.call_tool(&tool.name, serde_json::json!({})).await?;
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct Deadline<'a> {
+    protocol: &'a Protocol,
+    deadline: Instant,
+}
+
+impl<'a> Deadline<'a> {
+    pub(crate) fn new(protocol: &'a Protocol, deadline: Instant) -> Self {
+        Self { protocol, deadline }
+    }
+
+    /// Time left before the deadline, zero once it has passed
+    pub fn remaining(&self) -> Duration {
+        self.deadline.saturating_duration_since(Instant::now())
+    }
+
+    // Run `call`, failing with `Timeout` if it doesn't finish before the deadline
+    async fn run<T>(
+        &self,
+        call: impl Future<Output = Result<T, ClientError>>,
+    ) -> Result<T, ClientError> {
+        if self.remaining().is_zero() {
+            return Err(ClientError::Timeout);
+        }
+        tokio::time::timeout_at(self.deadline.into(), call)
+            .await
+            .map_err(|_| ClientError::Timeout)?
+    }
+
+    pub async fn call_tool(
+        &self,
+        name: &str,
+        arguments: serde_json::Value,
+    ) -> Result<CallToolResponse, ClientError> {
+        self.run(self.protocol.call_tool(name, arguments)).await
+    }
+
+    pub async fn ping(&self) -> Result<Duration, ClientError> {
+        self.run(self.protocol.ping()).await
+    }
+
+    pub async fn list_prompts(&self) -> Result<Vec<Prompt>, ClientError> {
+        self.run(self.protocol.list_prompts()).await
+    }
+
+    pub async fn list_resources(
+        &self,
+        cursor: Option<String>,
+    ) -> Result<ResourcesListResponse, ClientError> {
+        self.run(self.protocol.list_resources(cursor)).await
+    }
+
+    pub async fn read_resource(&self, uri: &str) -> Result<ResourcesReadResponse, ClientError> {
+        self.run(self.protocol.read_resource(uri)).await
+    }
+
+    pub async fn read_resources(
+        &self,
+        uris: Vec<String>,
+    ) -> Result<ResourcesReadResponse, ClientError> {
+        self.run(self.protocol.read_resources(uris)).await
+    }
+
+    pub async fn list_tools(&self) -> Result<ListToolsResponse, ClientError> {
+        self.run(self.protocol.list_tools()).await
+    }
+}
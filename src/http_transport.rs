@@ -0,0 +1,303 @@
+use crate::{ClientError, Transport};
+use futures::future::BoxFuture;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, ACCEPT, CONTENT_TYPE, USER_AGENT};
+use reqwest::{StatusCode, Url};
+use std::collections::HashMap;
+use std::sync::OnceLock;
+use tokio::sync::{mpsc, Mutex};
+use tokio::task::JoinHandle;
+
+// Header carrying the session id the server assigns during initialization
+const SESSION_HEADER: &str = "mcp-session-id";
+
+/// Talks to a server over the streamable HTTP transport. Every message is POSTed to the server's
+/// endpoint, and whatever the server sends back (a JSON body or a stream of Server-Sent Events)
+/// is handed to the client as it arrives, so responses and notifications take the same path as
+/// over stdio. Once initialized, a GET to the endpoint opens the server's own event stream for
+/// messages that aren't replies to a POST, unless the server answers 405.
+///
+/// # Example
+/// ```no_run
+/// # async fn run() -> Result<(), mcp_client_rs::ClientError> {
+/// use mcp_client_rs::{HttpTransport, Protocol};
+///
+/// let transport = HttpTransport::new("https://example.com/mcp", Default::default())?;
+/// let client = Protocol::builder().transport(transport).build().await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct HttpTransport {
+    // Built on first use, once every option is set
+    http: OnceLock<reqwest::Client>,
+    compression: bool,
+    url: Url,
+    headers: HeaderMap,
+    // Assigned by the server on initialization and echoed on every later request
+    session_id: std::sync::Mutex<Option<String>>,
+    // Cloned into the task reading each response body; taken on close so `recv` ends once
+    // those tasks finish
+    messages: std::sync::Mutex<Option<mpsc::UnboundedSender<String>>>,
+    incoming: Mutex<mpsc::UnboundedReceiver<String>>,
+    // Task reading the standalone event stream opened after initialization
+    listener: std::sync::Mutex<Option<JoinHandle<()>>>,
+}
+
+impl HttpTransport {
+    /// Connect to the MCP endpoint at `url`, sending `headers` (e.g. `Authorization`) with every
+    /// request. The `User-Agent` defaults to `mcp-client-rs/<version>` unless set in `headers`
+    /// or with [`user_agent`](Self::user_agent).
+    pub fn new(url: &str, headers: HashMap<String, String>) -> Result<Self, ClientError> {
+        let url = Url::parse(url).map_err(ClientError::transport)?;
+
+        let mut header_map = HeaderMap::new();
+        header_map.insert(
+            USER_AGENT,
+            HeaderValue::from_static(concat!("mcp-client-rs/", env!("CARGO_PKG_VERSION"))),
+        );
+        for (name, value) in headers {
+            header_map.insert(
+                HeaderName::try_from(name).map_err(ClientError::transport)?,
+                HeaderValue::try_from(value).map_err(ClientError::transport)?,
+            );
+        }
+
+        let (messages, incoming) = mpsc::unbounded_channel();
+        Ok(Self {
+            http: OnceLock::new(),
+            compression: true,
+            url,
+            headers: header_map,
+            session_id: std::sync::Mutex::new(None),
+            messages: std::sync::Mutex::new(Some(messages)),
+            incoming: Mutex::new(incoming),
+            listener: std::sync::Mutex::new(None),
+        })
+    }
+
+    /// Identify as `user_agent` in the `User-Agent` header of every request, e.g. for servers
+    /// that keep analytics or allowlists by client. Fails if it isn't a valid header value.
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Result<Self, ClientError> {
+        let user_agent =
+            HeaderValue::try_from(user_agent.into()).map_err(ClientError::transport)?;
+        self.headers.insert(USER_AGENT, user_agent);
+        Ok(self)
+    }
+
+    /// Ask the server for gzip-compressed responses and decompress them before parsing, which
+    /// saves bandwidth on large resource contents. On by default.
+    pub fn compression(mut self, enabled: bool) -> Self {
+        self.compression = enabled;
+        self
+    }
+
+    fn request(&self, method: reqwest::Method) -> Result<reqwest::RequestBuilder, ClientError> {
+        let http = match self.http.get() {
+            Some(http) => http,
+            None => {
+                let http = reqwest::Client::builder()
+                    .gzip(self.compression)
+                    .build()
+                    .map_err(ClientError::transport)?;
+                // Another request may have built one first, which is just as good
+                self.http.get_or_init(|| http)
+            }
+        };
+        let request = http
+            .request(method, self.url.clone())
+            .headers(self.headers.clone());
+        Ok(match self.session_id.lock().unwrap().as_deref() {
+            Some(session_id) => request.header(SESSION_HEADER, session_id),
+            None => request,
+        })
+    }
+}
+
+impl Transport for HttpTransport {
+    fn send<'a>(&'a self, message: &'a str) -> BoxFuture<'a, Result<(), ClientError>> {
+        Box::pin(async move {
+            let Some(messages) = self.messages.lock().unwrap().clone() else {
+                return Err(ClientError::ServerClosed { stderr: vec![] });
+            };
+
+            let response = self
+                .request(reqwest::Method::POST)?
+                .header(CONTENT_TYPE, "application/json")
+                .header(ACCEPT, "application/json, text/event-stream")
+                .body(message.to_string())
+                .send()
+                .await?;
+
+            if let Some(session_id) = response
+                .headers()
+                .get(SESSION_HEADER)
+                .and_then(|id| id.to_str().ok())
+            {
+                *self.session_id.lock().unwrap() = Some(session_id.to_string());
+            }
+            match response.status() {
+                // Notifications and responses to server requests are only acknowledged
+                StatusCode::ACCEPTED => {}
+                // The server ended the session; it has to be initialized again
+                StatusCode::NOT_FOUND if self.session_id.lock().unwrap().is_some() => {
+                    return Err(ClientError::ServerClosed { stderr: vec![] })
+                }
+                status if !status.is_success() => {
+                    return Err(ClientError::transport(format!(
+                        "Server responded with HTTP {}",
+                        status
+                    )))
+                }
+                _ => {
+                    tokio::spawn(read_body(response, messages.clone()));
+                }
+            }
+
+            // The server can only push messages of its own once the session is initialized
+            if is_initialized_notification(message) {
+                let request = self
+                    .request(reqwest::Method::GET)?
+                    .header(ACCEPT, "text/event-stream");
+                let listener = tokio::spawn(listen(request, messages));
+                if let Some(previous) = self.listener.lock().unwrap().replace(listener) {
+                    previous.abort();
+                }
+            }
+            Ok(())
+        })
+    }
+
+    fn recv(&self) -> BoxFuture<'_, Result<Option<String>, ClientError>> {
+        Box::pin(async move { Ok(self.incoming.lock().await.recv().await) })
+    }
+
+    fn close(&self) -> BoxFuture<'_, Result<(), ClientError>> {
+        Box::pin(async move {
+            self.messages.lock().unwrap().take();
+            if let Some(listener) = self.listener.lock().unwrap().take() {
+                listener.abort();
+            }
+            // Let the server free the session. Servers may not allow this, so the outcome
+            // doesn't matter.
+            if self.session_id.lock().unwrap().is_some() {
+                let deleted = async {
+                    self.request(reqwest::Method::DELETE)?.send().await?;
+                    Ok::<_, ClientError>(())
+                };
+                if let Err(err) = deleted.await {
+                    tracing::debug!(error = %err, "Failed to end HTTP session");
+                }
+            }
+            Ok(())
+        })
+    }
+}
+
+impl Drop for HttpTransport {
+    // The event stream holds a connection open until the server ends it
+    fn drop(&mut self) {
+        if let Some(listener) = self.listener.get_mut().unwrap().take() {
+            listener.abort();
+        }
+    }
+}
+
+fn is_initialized_notification(message: &str) -> bool {
+    serde_json::from_str::<serde_json::Value>(message)
+        .is_ok_and(|message| message["method"] == "notifications/initialized")
+}
+
+// Open the server's standalone event stream and forward its messages
+async fn listen(request: reqwest::RequestBuilder, messages: mpsc::UnboundedSender<String>) {
+    match request.send().await {
+        Ok(response) if response.status() == StatusCode::METHOD_NOT_ALLOWED => {
+            tracing::debug!("Server offers no standalone event stream");
+        }
+        Ok(response) if !response.status().is_success() => {
+            tracing::warn!(status = %response.status(), "Server refused the event stream");
+        }
+        Ok(response) => read_body(response, messages).await,
+        Err(err) => tracing::warn!(error = %err, "Failed to open the event stream"),
+    }
+}
+
+// Forward the messages in a POST response body: either one JSON body or an SSE stream whose
+// events each carry a message
+async fn read_body(mut response: reqwest::Response, messages: mpsc::UnboundedSender<String>) {
+    let is_event_stream = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|content_type| content_type.to_str().ok())
+        .is_some_and(|content_type| content_type.starts_with("text/event-stream"));
+
+    if !is_event_stream {
+        match response.text().await {
+            Ok(body) if !body.trim().is_empty() => {
+                let _ = messages.send(body);
+            }
+            Ok(_) => {}
+            Err(err) => tracing::warn!(error = %err, "Failed to read HTTP response"),
+        }
+        return;
+    }
+
+    let mut events = SseParser::default();
+    loop {
+        match response.chunk().await {
+            Ok(Some(chunk)) => {
+                for data in events.push(&chunk) {
+                    let _ = messages.send(data);
+                }
+            }
+            Ok(None) => return,
+            Err(err) => {
+                tracing::warn!(error = %err, "Event stream from server ended early");
+                return;
+            }
+        }
+    }
+}
+
+// Incremental parser for `text/event-stream` bodies, yielding the data of each `message` event
+#[derive(Default)]
+struct SseParser {
+    // Bytes of a line not yet terminated
+    pending: Vec<u8>,
+    data: Vec<String>,
+    event: Option<String>,
+}
+
+impl SseParser {
+    // Feed the next chunk of the stream, returning the data of every event it completes
+    fn push(&mut self, chunk: &[u8]) -> Vec<String> {
+        self.pending.extend_from_slice(chunk);
+        let mut events = vec![];
+        while let Some(end) = self.pending.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.pending.drain(..=end).collect();
+            let line = String::from_utf8_lossy(&line);
+            let line = line.trim_end_matches(['\n', '\r']);
+
+            if line.is_empty() {
+                let event = self.event.take();
+                let data = std::mem::take(&mut self.data);
+                if !data.is_empty() && event.as_deref().is_none_or(|e| e == "message") {
+                    events.push(data.join("\n"));
+                }
+                continue;
+            }
+            // Lines starting with a colon are comments, often sent as keepalives
+            if line.starts_with(':') {
+                continue;
+            }
+            let (field, value) = match line.split_once(':') {
+                Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+                None => (line, ""),
+            };
+            match field {
+                "data" => self.data.push(value.to_string()),
+                "event" => self.event = Some(value.to_string()),
+                _ => {}
+            }
+        }
+        events
+    }
+}
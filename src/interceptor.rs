@@ -0,0 +1,9 @@
+/// Middleware that can observe or modify JSON-RPC traffic, e.g. to refresh auth tokens or log
+/// messages. Interceptors run in registration order.
+pub trait Interceptor: Send + Sync {
+    /// Called with each outbound request before it is written
+    fn on_request(&self, _request: &mut serde_json::Value) {}
+
+    /// Called with each inbound response before it is deserialized
+    fn on_response(&self, _response: &mut serde_json::Value) {}
+}
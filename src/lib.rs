@@ -1,10 +1,31 @@
+mod deadline;
+#[cfg(feature = "http")]
+mod http_transport;
+mod interceptor;
 mod protocol;
+mod protocol_builder;
 mod protocol_manager;
+mod retry;
+mod server_log;
+mod transport;
 mod types;
 
-pub use protocol::Protocol;
-pub use protocol_manager::ProtocolManager;
+pub use deadline::Deadline;
+#[cfg(feature = "http")]
+pub use http_transport::HttpTransport;
+pub use interceptor::Interceptor;
+pub use protocol::{Protocol, ToolCall, DEFAULT_REQUEST_TIMEOUT};
+pub use protocol_builder::{ProtocolBuilder, DEFAULT_PROTOCOL_VERSION};
+pub use protocol_manager::{Callable, CallableOutput, ProtocolManager};
+pub use retry::{ExponentialBackoff, RetryPolicy};
+pub use transport::{StdioTransport, Transport, SHUTDOWN_GRACE};
 pub use types::{
-    CallToolResponse, ClientError, ListToolsResponse, Prompt, ResourcesListResponse,
-    ResourcesReadResponse, ServerCapabilities, ServerCapability, Tool, ToolResponseContent,
+    Annotations, CallToolResponse, ClientCapabilities, ClientError, ClientSamplingCapability,
+    CompletionResponse, ConnectionState, CreateMessageParams, CreateMessageResult, Direction,
+    JsonRpcError, JsonRpcResponse, ListToolsResponse, LogEntry, LogLevel, LogSource, McpConfig,
+    McpServerConfig, ProgressUpdate, Prompt, PromptArgument, PromptGetResponse, PromptMessage,
+    RequestType, Resource, ResourceContents, ResourceLink, ResourceTemplate,
+    ResourceTemplatesResponse, ResourcesListResponse, ResourcesReadResponse, Role, Root,
+    RootsCapability, SamplingMessage, ServerCapabilities, ServerCapability, ServerInfo,
+    ServerNotification, ServerProfile, StopReason, Tool, ToolResponseContent,
 };
@@ -1,9 +1,6 @@
-mod protocol;
-mod types;
 use dotenv::dotenv;
-use protocol::Protocol;
+use mcp_client_rs::{ClientError, Protocol};
 use std::{collections::HashMap, sync::Arc};
-use types::ClientError;
 
 #[tokio::main]
 async fn main() -> Result<(), ClientError> {
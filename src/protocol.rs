@@ -1,97 +1,1126 @@
+use crate::server_log::LogBuffer;
+use crate::transport::{StdioTransport, Transport, STDERR_DRAIN_GRACE};
 use crate::types::{
-    CallToolResponse, ClientError, ClientInfo, InitializeParams, InitializeResponse,
-    JsonRpcRequest, JsonRpcResponse, ListToolsResponse, Prompt, RequestType, ResourcesListResponse,
-    ResourcesReadResponse, ResponseContent, ServerCapabilities, ServerCapability, ToolCallParams,
+    CallToolResponse, ClientCapabilities, ClientError, ClientInfo, CompleteResult,
+    CompletionResponse, ConnectionState, CreateMessageParams, CreateMessageResult, Direction,
+    InitializeParams, InitializeResponse, JsonRpcError, JsonRpcNotification, JsonRpcRequest,
+    JsonRpcResponse, ListPromptsResponse, ListToolsResponse, LogEntry, LogLevel, LogSource,
+    ProgressUpdate, Prompt, PromptGetResponse, PromptMessage, RequestType, Resource,
+    ResourceTemplatesResponse, ResourcesListResponse, ResourcesReadResponse, ResponseContent, Root,
+    ServerCapabilities, ServerCapability, ServerInfo, ServerNotification, ServerProfile, Tool,
+    ToolCallParams, ToolResponseContent,
 };
-use serde::Serialize;
+use crate::{
+    Deadline, ExponentialBackoff, Interceptor, ProtocolBuilder, RetryPolicy,
+    DEFAULT_PROTOCOL_VERSION,
+};
+use futures::future::{self, BoxFuture};
+use futures::{stream, Stream, StreamExt};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use serde_json::json;
+#[cfg(feature = "lenient-json")]
+use std::sync::atomic::AtomicBool;
 use std::{
-    collections::HashMap,
-    process::Stdio,
+    collections::{BTreeSet, HashMap},
+    future::Future,
+    pin::Pin,
     sync::{
         atomic::{AtomicU64, Ordering},
-        Arc,
+        Arc, Weak,
     },
+    task::{Context, Poll},
+    time::{Duration, Instant},
 };
-use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
-use tokio::sync::Mutex;
+use tokio::sync::{broadcast, mpsc, oneshot, watch, Semaphore};
+use tokio::task::JoinHandle;
+
+// Number of reads `read_resources_concurrent` runs at once when no limit is given
+const DEFAULT_READ_CONCURRENCY: usize = 4;
+
+// JSON-RPC error code for a method the server doesn't implement
+const METHOD_NOT_FOUND: i32 = -32601;
+
+// JSON-RPC error code for a request whose params are malformed
+const INVALID_PARAMS: i32 = -32602;
+
+// JSON-RPC error code for a failure on our side while answering a request
+const INTERNAL_ERROR: i32 = -32603;
+
+// Error code sent when the sampling handler declines a request
+const SAMPLING_REJECTED: i32 = -1;
+
+// Error code sent when too many server requests are already being handled
+const CLIENT_BUSY: i32 = -32000;
+
+// Server-to-client requests handled at once unless configured otherwise
+const DEFAULT_MAX_PENDING_SERVER_REQUESTS: usize = 32;
+
+/// How long a request waits for its response before failing with [`ClientError::Timeout`],
+/// unless configured otherwise
+pub const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+// Stderr lines included in the error when the server goes away
+const STDERR_TAIL_LINES: usize = 20;
+
+// Notifications buffered per subscriber before the slowest starts missing them
+const NOTIFICATION_CAPACITY: usize = 64;
+
+// Weight of the newest sample in the ping latency moving average
+const LATENCY_SMOOTHING: f64 = 0.2;
+
+// Delivers a request's response, or the error that prevented one
+type Responder = oneshot::Sender<Result<serde_json::Value, ClientError>>;
+
+// Requests awaiting a response, keyed by id, with their method, send time and responder
+type InFlightMap = std::sync::Mutex<HashMap<u64, (RequestType, Instant, Responder)>>;
+
+// Answers `sampling/createMessage` requests from the server
+type SamplingHandler = Arc<
+    dyn Fn(CreateMessageParams) -> BoxFuture<'static, Result<CreateMessageResult, String>>
+        + Send
+        + Sync,
+>;
+
+// Sees every message as it is sent or received
+pub(crate) type RawMessageHook = Arc<dyn Fn(Direction, &str) + Send + Sync>;
+
+// Spawns a fresh server recording its stderr in the given buffer, for restarts
+pub(crate) type Respawn =
+    Arc<dyn Fn(Arc<LogBuffer>) -> Result<Arc<dyn Transport>, ClientError> + Send + Sync>;
+
+// How a server that exited is brought back
+#[derive(Clone)]
+struct Restart {
+    respawn: Respawn,
+    // Delays between attempts, and how many fail in a row before giving up
+    backoff: ExponentialBackoff,
+    // Shared with the protocol handles, for the ids of the replayed handshake
+    next_id: Arc<AtomicU64>,
+    // How long the replayed handshake may take
+    timeout: Duration,
+}
+
+// Called with the previous (if any) and new capabilities whenever they are replaced
+pub(crate) type CapabilitiesCallback =
+    Arc<dyn Fn(Option<&ServerCapabilities>, &ServerCapabilities) + Send + Sync>;
 
 pub struct Protocol {
-    // Protect stdin/stdout with a mutex for exclusive access
-    inner: Arc<Mutex<Client>>,
-    // Atomic counter for generating unique request IDs
-    next_id: AtomicU64,
+    // Connection to the server, shared with the idle ping task
+    inner: Arc<Client>,
+    // Atomic counter for generating unique request IDs, shared with the idle ping task
+    next_id: Arc<AtomicU64>,
+    // Identity and capabilities sent to the server during initialization
+    client_info: ClientInfo,
+    client_capabilities: ClientCapabilities,
     // Server capabilities received during initialization
     capabilities: Option<ServerCapabilities>,
+    // Server identity and negotiated protocol version from initialization
+    server_info: Option<ServerInfo>,
+    protocol_version: Option<String>,
+    // Meta merged into every request's `params._meta`
+    default_meta: Option<serde_json::Value>,
+    // Results of the last `probe_methods`
+    method_support: std::sync::Mutex<Option<HashMap<RequestType, bool>>>,
+    // Caps how many requests are awaiting a response at once; unlimited when unset. Shared
+    // between handles since the limit is per server.
+    concurrency_limit: Option<Arc<Semaphore>>,
+    // How long each request waits for its response
+    request_timeout: Duration,
+    // Consulted when a tool call fails; no retries when unset
+    retry_policy: Option<Arc<dyn RetryPolicy>>,
+    // Middleware run on every request and response, in registration order
+    interceptors: Vec<Arc<dyn Interceptor>>,
+    // Skip capability pre-flight checks and let the server's response decide
+    trust_methods_over_capabilities: bool,
+    // Background task pinging the server when the connection goes idle
+    idle_ping: Option<JoinHandle<()>>,
+    // Notified when initialization replaces the stored capabilities
+    on_capabilities_change: Option<CapabilitiesCallback>,
 }
 
-// Inner state protected by the mutex
+// Connection to the server, shared with the idle ping task
 struct Client {
-    stdin: tokio::process::ChildStdin,
-    stdout: BufReader<tokio::process::ChildStdout>,
-    _child: tokio::process::Child,
+    // Replaced when the server is restarted
+    transport: std::sync::RwLock<Arc<dyn Transport>>,
+    // Task reading from the transport, stopped when the connection is dropped
+    reader: std::sync::Mutex<Option<JoinHandle<()>>>,
+    // Closed once the server stops reading our requests or closes its output and isn't
+    // restarted; nothing more is sent
+    state: watch::Sender<ConnectionState>,
+    // Respawns the server when it exits; unset unless a restart policy is configured
+    restart: std::sync::Mutex<Option<Restart>>,
+    // Params of the last successful initialize, replayed to a restarted server
+    handshake: std::sync::Mutex<Option<serde_json::Value>>,
+    // Requests awaiting a response, each with the channel its response is delivered on
+    in_flight: InFlightMap,
+    // Time of the last frame read from the server
+    last_activity: std::sync::Mutex<Instant>,
+    // Smoothed round-trip time of successful pings, idle pings included
+    latency: std::sync::Mutex<Option<Duration>>,
+    // Requests from the server waiting to be answered by the inbound task
+    inbound: mpsc::UnboundedSender<serde_json::Value>,
+    // One permit per server request being handled; requests finding none are refused
+    server_request_slots: std::sync::Mutex<Arc<Semaphore>>,
+    // Roots returned to the server on `roots/list`
+    roots: std::sync::Mutex<Vec<Root>>,
+    // Uris with an active `resources/subscribe`
+    subscriptions: std::sync::Mutex<BTreeSet<String>>,
+    // Generates messages for the server's `sampling/createMessage` requests
+    sampling: std::sync::Mutex<Option<SamplingHandler>>,
+    // Sees every message sent or received
+    raw_hook: std::sync::Mutex<Option<RawMessageHook>>,
+    // Recent stderr output and log notifications from the server
+    logs: Arc<LogBuffer>,
+    // Notifications from the server, fanned out to every subscriber
+    notifications: broadcast::Sender<ServerNotification>,
+    // Fall back to JSON5 when a response isn't strict JSON
+    #[cfg(feature = "lenient-json")]
+    lenient_json: AtomicBool,
+}
+
+impl Client {
+    // Send one message, treating a transport that reports the server gone as closing the
+    // connection
+    async fn write(&self, message: &str) -> Result<(), ClientError> {
+        self.connected().await?;
+        self.observe(Direction::Outbound, message);
+        let sent = self.transport().send(message).await;
+        self.check_sent(sent)
+    }
+
+    async fn write_raw(&self, bytes: &[u8]) -> Result<(), ClientError> {
+        self.connected().await?;
+        self.observe(Direction::Outbound, &String::from_utf8_lossy(bytes));
+        let sent = self.transport().send_raw(bytes).await;
+        self.check_sent(sent)
+    }
+
+    fn transport(&self) -> Arc<dyn Transport> {
+        self.transport.read().unwrap().clone()
+    }
+
+    fn is_closed(&self) -> bool {
+        *self.state.borrow() == ConnectionState::Closed
+    }
+
+    // Wait out a restart in progress, failing if the connection is closed instead
+    async fn connected(&self) -> Result<(), ClientError> {
+        let connected = self
+            .state
+            .subscribe()
+            .wait_for(|state| *state != ConnectionState::Reconnecting)
+            .await
+            .is_ok_and(|state| *state == ConnectionState::Connected);
+        if connected {
+            Ok(())
+        } else {
+            Err(self.closed_error())
+        }
+    }
+
+    // Fold a ping's round-trip time into the moving average
+    fn record_latency(&self, elapsed: Duration) {
+        let mut latency = self.latency.lock().unwrap();
+        *latency = Some(match *latency {
+            Some(average) => {
+                average.mul_f64(1.0 - LATENCY_SMOOTHING) + elapsed.mul_f64(LATENCY_SMOOTHING)
+            }
+            None => elapsed,
+        });
+    }
+
+    // Show a message to the raw message hook, if one is set
+    fn observe(&self, direction: Direction, message: &str) {
+        let hook = self.raw_hook.lock().unwrap().clone();
+        if let Some(hook) = hook {
+            hook(direction, message);
+        }
+    }
+
+    fn check_sent(&self, sent: Result<(), ClientError>) -> Result<(), ClientError> {
+        match sent {
+            Err(ClientError::ServerClosed { .. }) => {
+                // A server being restarted is only closed if the restart fails, which the
+                // reader notices once it sees the end of output
+                if self.restart.lock().unwrap().is_none() {
+                    self.state.send_replace(ConnectionState::Closed);
+                }
+                Err(self.closed_error())
+            }
+            sent => sent,
+        }
+    }
+
+    // Send `message` and wait for the reader task to deliver the response to request `id`.
+    // Requests cut off by a server restart are sent again if that is safe.
+    async fn exchange(
+        &self,
+        message: &str,
+        id: u64,
+        method: RequestType,
+    ) -> Result<serde_json::Value, ClientError> {
+        loop {
+            match self.exchange_once(message, id, method.clone()).await {
+                Err(ClientError::Disconnected) if method.is_idempotent() => {
+                    tracing::debug!(id, "Resending request after server restart");
+                }
+                result => return result,
+            }
+        }
+    }
+
+    async fn exchange_once(
+        &self,
+        message: &str,
+        id: u64,
+        method: RequestType,
+    ) -> Result<serde_json::Value, ClientError> {
+        if self.is_closed() {
+            return Err(self.closed_error());
+        }
+        let (responder, response) = oneshot::channel();
+        let _in_flight = InFlightGuard::register(&self.in_flight, id, method, responder);
+        if let Err(err) = self.write(message).await {
+            if !matches!(err, ClientError::ServerClosed { .. }) {
+                return Err(err);
+            }
+            // The reader fails this request when it starts restarting the server
+            if self.restart.lock().unwrap().is_some() {
+                return response.await.unwrap_or_else(|_| Err(self.closed_error()));
+            }
+            // A broken pipe usually means the server exited. The reader fails this request
+            // once it sees the end of output, by which time stderr has been collected.
+            return match tokio::time::timeout(STDERR_DRAIN_GRACE, response).await {
+                Ok(Ok(result)) => result,
+                _ => Err(self.closed_error()),
+            };
+        }
+        // The sender is only dropped unanswered when the reader stops at end of output
+        response.await.unwrap_or_else(|_| Err(self.closed_error()))
+    }
+
+    // Route one line read from the server to the requests it answers. Lines that aren't JSON
+    // (blank lines, log output, partial writes) and responses that can't be attributed to a
+    // request are dropped.
+    fn dispatch(&self, line: &str) {
+        *self.last_activity.lock().unwrap() = Instant::now();
+        self.observe(Direction::Inbound, line.trim_end_matches(['\r', '\n']));
+
+        let messages = match self.parse_frame(line) {
+            Ok(serde_json::Value::Array(batch)) => batch,
+            Ok(message) => vec![message],
+            Err(err) => {
+                if !line.trim().is_empty() {
+                    tracing::warn!(error = %err, line = line.trim_end(), "Ignoring a line that isn't JSON");
+                }
+                return;
+            }
+        };
+        for message in messages {
+            if message.get("method").is_some() {
+                if message.get("id").is_some() {
+                    self.queue_request(message);
+                } else {
+                    self.notify(message);
+                }
+                continue;
+            }
+            match message.get("id").and_then(|id| id.as_u64()) {
+                Some(id) => {
+                    let in_flight = self.in_flight.lock().unwrap().remove(&id);
+                    match in_flight {
+                        Some((_, _, responder)) => {
+                            let _ = responder.send(Ok(message));
+                        }
+                        None => tracing::debug!(id, "Dropping response nobody is waiting for"),
+                    }
+                }
+                None => self.route_null_id_error(message),
+            }
+        }
+    }
+
+    // Record log messages and pass the notification on to subscribers
+    fn notify(&self, mut message: serde_json::Value) {
+        let method = message["method"].as_str().unwrap_or_default().to_string();
+        let params = message
+            .get_mut("params")
+            .map(serde_json::Value::take)
+            .unwrap_or(serde_json::Value::Null);
+        let notification = ServerNotification::parse(&method, params);
+
+        if let ServerNotification::Message {
+            level,
+            logger,
+            data,
+        } = &notification
+        {
+            let text = match data {
+                serde_json::Value::String(text) => text.clone(),
+                data => data.to_string(),
+            };
+            let text = match logger {
+                Some(logger) => format!("{}: {}", logger, text),
+                None => text,
+            };
+            self.logs.push(LogSource::Notification, *level, text);
+        }
+        // Sending only fails when nobody is subscribed
+        let _ = self.notifications.send(notification);
+    }
+
+    // Hand a server-to-client request to the inbound task, keeping the reader free to deliver
+    // responses while it is answered
+    fn queue_request(&self, request: serde_json::Value) {
+        // Only fails once the inbound task has stopped, when the connection is being dropped
+        let _ = self.inbound.send(request);
+    }
+
+    // Answer a request the server sent to us
+    async fn handle_request(&self, request: &serde_json::Value) -> serde_json::Value {
+        let id = request["id"].clone();
+        let error = |code: i32, message: String| json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } });
+        match request["method"].as_str().unwrap_or_default() {
+            "roots/list" => {
+                let roots = self.roots.lock().unwrap().clone();
+                json!({ "jsonrpc": "2.0", "id": id, "result": { "roots": roots } })
+            }
+            "ping" => json!({ "jsonrpc": "2.0", "id": id, "result": {} }),
+            "sampling/createMessage" if self.sampling.lock().unwrap().is_some() => {
+                let params = match CreateMessageParams::deserialize(&request["params"]) {
+                    Ok(params) => params,
+                    Err(e) => return error(INVALID_PARAMS, format!("Invalid params: {}", e)),
+                };
+                let Some(handler) = self.sampling.lock().unwrap().clone() else {
+                    return error(METHOD_NOT_FOUND, "Sampling is not supported".to_string());
+                };
+                let result = match handler(params).await {
+                    Ok(result) => result,
+                    Err(message) => return error(SAMPLING_REJECTED, message),
+                };
+                // A sampled message can only be text or an image
+                let kind = match &result.content {
+                    ToolResponseContent::Text { .. } | ToolResponseContent::Image { .. } => {
+                        return json!({ "jsonrpc": "2.0", "id": id, "result": result })
+                    }
+                    ToolResponseContent::Resource { .. } => "resource",
+                    ToolResponseContent::ResourceLink(_) => "resource_link",
+                };
+                tracing::warn!(
+                    kind,
+                    "Sampling handler returned content a sampling result can't carry"
+                );
+                error(
+                    INTERNAL_ERROR,
+                    format!(
+                        "Sampling produced {} content, only text and image are allowed",
+                        kind
+                    ),
+                )
+            }
+            method => error(METHOD_NOT_FOUND, format!("Method not found: {}", method)),
+        }
+    }
+
+    // A server that can't parse a request answers with an error and a null id. That can only
+    // be attributed to a request when it is the one in flight; anything else is dropped.
+    fn route_null_id_error(&self, message: serde_json::Value) {
+        let is_error = message.get("id").is_some_and(serde_json::Value::is_null)
+            && message
+                .get("error")
+                .is_some_and(|error| JsonRpcError::deserialize(error).is_ok());
+        let mut in_flight = self.in_flight.lock().unwrap();
+        let mut ids = in_flight.keys();
+        let (true, Some(&only), None) = (is_error, ids.next(), ids.next()) else {
+            tracing::debug!(%message, "Dropping response without a request id");
+            return;
+        };
+        if let Some((_, _, responder)) = in_flight.remove(&only) {
+            let _ = responder.send(Ok(message));
+        }
+    }
+
+    // Mark the connection closed and fail everything still waiting
+    fn close(&self) {
+        self.state.send_replace(ConnectionState::Closed);
+        for (_, (_, _, responder)) in self.in_flight.lock().unwrap().drain() {
+            let _ = responder.send(Err(self.closed_error()));
+        }
+    }
+
+    // Called when the server's output ends. Returns how to restart the server, having failed
+    // everything still waiting with `Disconnected`, or `None` if the connection is over.
+    fn begin_restart(&self) -> Option<Restart> {
+        if self.is_closed() {
+            return None;
+        }
+        let restart = self.restart.lock().unwrap().clone()?;
+        tracing::warn!("Server exited, restarting it");
+        self.state.send_replace(ConnectionState::Reconnecting);
+        for (_, (_, _, responder)) in self.in_flight.lock().unwrap().drain() {
+            let _ = responder.send(Err(ClientError::Disconnected));
+        }
+        Some(restart)
+    }
+
+    // Spawn a new server and replay the handshake, then switch over to it. Returns the new
+    // transport for the reader.
+    async fn reconnect(&self, restart: &Restart) -> Result<Arc<dyn Transport>, ClientError> {
+        let transport = (restart.respawn)(self.logs.clone())?;
+        tokio::time::timeout(
+            restart.timeout,
+            self.replay_handshake(transport.as_ref(), &restart.next_id),
+        )
+        .await
+        .map_err(|_| ClientError::Timeout)??;
+
+        *self.transport.write().unwrap() = transport.clone();
+        // Unless the connection was shut down meanwhile
+        let reconnected = self.state.send_if_modified(|state| {
+            let reconnecting = *state == ConnectionState::Reconnecting;
+            if reconnecting {
+                *state = ConnectionState::Connected;
+            }
+            reconnecting
+        });
+        if !reconnected {
+            transport.close().await?;
+            return Err(self.closed_error());
+        }
+        tracing::info!("Restarted server");
+
+        // The new server knows nothing of the old one's subscriptions. Their responses go
+        // unclaimed.
+        let subscriptions = self.subscriptions.lock().unwrap().clone();
+        for uri in subscriptions {
+            let id = restart.next_id.fetch_add(1, Ordering::Relaxed);
+            let request =
+                JsonRpcRequest::new(id, RequestType::ResourcesSubscribe, json!({ "uri": uri }));
+            let message = serde_json::to_string(&request)
+                .map_err(|e| ClientError::SerializationError(e.to_string()))?;
+            self.write(&message).await?;
+        }
+        Ok(transport)
+    }
+
+    // Run the last initialize handshake again on a freshly spawned server. Reads directly from
+    // `transport`, since the reader isn't using it yet; anything else the server sends
+    // meanwhile is dispatched as usual.
+    async fn replay_handshake(
+        &self,
+        transport: &dyn Transport,
+        next_id: &AtomicU64,
+    ) -> Result<(), ClientError> {
+        let Some(params) = self.handshake.lock().unwrap().clone() else {
+            // Never initialized, so neither is the new server
+            return Ok(());
+        };
+        let id = next_id.fetch_add(1, Ordering::Relaxed);
+        let request = JsonRpcRequest::new(id, RequestType::Initialize, params);
+        let message = serde_json::to_string(&request)
+            .map_err(|e| ClientError::SerializationError(e.to_string()))?;
+        self.observe(Direction::Outbound, &message);
+        transport.send(&message).await?;
+
+        loop {
+            let Some(line) = transport.recv().await? else {
+                return Err(self.closed_error());
+            };
+            let Ok(response) = self.parse_frame(&line) else {
+                self.dispatch(&line);
+                continue;
+            };
+            if response.get("method").is_some() || response["id"] != id {
+                self.dispatch(&line);
+                continue;
+            }
+            self.observe(Direction::Inbound, line.trim_end_matches(['\r', '\n']));
+            serde_json::from_value::<JsonRpcResponse<serde_json::Value>>(response)
+                .map_err(|e| {
+                    ClientError::ProtocolError(format!("Failed to parse response: {}", e))
+                })?
+                .into_result()?;
+            break;
+        }
+
+        let notification = JsonRpcNotification::new(RequestType::Initialized, json!({}));
+        let message = serde_json::to_string(&notification)
+            .map_err(|e| ClientError::SerializationError(e.to_string()))?;
+        self.observe(Direction::Outbound, &message);
+        transport.send(&message).await
+    }
+
+    // The error for requests that can't complete because the server went away, carrying the
+    // last things it wrote to stderr
+    fn closed_error(&self) -> ClientError {
+        ClientError::ServerClosed {
+            stderr: self.logs.stderr_tail(STDERR_TAIL_LINES),
+        }
+    }
+
+    // Parse a line read from the server, falling back to JSON5 when lenient parsing is enabled
+    fn parse_frame(&self, line: &str) -> Result<serde_json::Value, ClientError> {
+        let strict = serde_json::from_str(line);
+
+        #[cfg(feature = "lenient-json")]
+        if strict.is_err() && self.lenient_json.load(Ordering::Relaxed) {
+            if let Ok(value) = json5::from_str(line) {
+                return Ok(value);
+            }
+        }
+
+        strict.map_err(|e| ClientError::ProtocolError(format!("Failed to parse response: {}", e)))
+    }
+}
+
+impl Drop for Client {
+    // Stop the reader so the transport is dropped too, which for stdio kills the server
+    fn drop(&mut self) {
+        if let Some(reader) = self.reader.get_mut().unwrap().take() {
+            reader.abort();
+        }
+    }
+}
+
+// Answer server-to-client requests off the reader task, each in its own task so a slow sampling
+// handler doesn't hold up the others. Requests beyond the configured limit are refused with a
+// busy error rather than queued, so the server isn't left waiting on its own timeout.
+async fn inbound_loop(
+    mut requests: mpsc::UnboundedReceiver<serde_json::Value>,
+    client: Weak<Client>,
+) {
+    while let Some(request) = requests.recv().await {
+        let Some(client) = client.upgrade() else {
+            return;
+        };
+        let slot = client
+            .server_request_slots
+            .lock()
+            .unwrap()
+            .clone()
+            .try_acquire_owned();
+        tokio::spawn(async move {
+            let response = match slot {
+                Ok(_slot) => client.handle_request(&request).await,
+                Err(_) => {
+                    tracing::warn!("Too many server requests being handled, refusing one");
+                    json!({
+                        "jsonrpc": "2.0",
+                        "id": request["id"],
+                        "error": { "code": CLIENT_BUSY, "message": "Client is busy" }
+                    })
+                }
+            };
+            if let Err(err) = client.write(&response.to_string()).await {
+                tracing::warn!(error = %err, "Failed to answer server request");
+            }
+        });
+    }
+}
+
+// Receive messages from the server and hand each to the connection for dispatch, restarting
+// the server when its output ends if a restart policy is configured. Holds only a weak
+// reference to the connection so dropping the last `Protocol` handle still drops it.
+async fn read_loop(mut transport: Arc<dyn Transport>, client: Weak<Client>) {
+    loop {
+        let received = transport.recv().await;
+        let restart = {
+            let Some(client) = client.upgrade() else {
+                return;
+            };
+            match received {
+                Ok(Some(message)) => {
+                    tracing::debug!(bytes = message.len(), "Received message");
+                    client.dispatch(&message);
+                    continue;
+                }
+                Ok(None) => {}
+                Err(err) => tracing::warn!(error = %err, "Failed to read from server"),
+            }
+            match client.begin_restart() {
+                Some(restart) => restart,
+                None => return client.close(),
+            }
+        };
+        match restart_server(&client, &restart).await {
+            Some(restarted) => transport = restarted,
+            None => return,
+        }
+    }
+}
+
+// Try to restart the server until the restart policy gives up, which closes the connection.
+// Returns the new server's transport, or `None` if the connection is gone.
+async fn restart_server(client: &Weak<Client>, restart: &Restart) -> Option<Arc<dyn Transport>> {
+    let mut attempt: u32 = 0;
+    loop {
+        attempt = attempt.saturating_add(1);
+        let Some(delay) = restart.backoff.delay(attempt) else {
+            tracing::warn!(attempts = attempt - 1, "Giving up restarting server");
+            client.upgrade()?.close();
+            return None;
+        };
+        tokio::time::sleep(delay).await;
+
+        let client = client.upgrade()?;
+        if client.is_closed() {
+            return None;
+        }
+        match client.reconnect(restart).await {
+            Ok(transport) => return Some(transport),
+            Err(_) if client.is_closed() => return None,
+            Err(err) => tracing::warn!(attempt, error = %err, "Failed to restart server"),
+        }
+    }
 }
 
 impl Protocol {
+    /// Spawn `program` and run the initialize handshake. The server inherits this process's
+    /// environment with `envs` set on top; see [`Self::builder`] to restrict what it inherits.
     pub async fn new(
         version: &str,
         program: &str,
         args: Vec<&str>,
         envs: HashMap<String, String>,
     ) -> Result<Self, ClientError> {
-        let mut child = tokio::process::Command::new(program)
-            .args(args)
-            .envs(envs)
-            .stdin(Stdio::piped())
-            .stdout(Stdio::piped())
-            .spawn()?;
+        let mut client = Self::spawn(program, args, envs)?;
+        client.initialize(version).await?;
 
-        let stdin = child.stdin.take().expect("Failed to get stdin");
-        let stdout = child.stdout.take().expect("Failed to get stdout");
+        Ok(client)
+    }
 
-        let inner = Client {
-            stdin,
-            stdout: BufReader::new(stdout),
-            _child: child,
+    /// Spawn a server, call a single tool, and shut the server down again with
+    /// [`Self::shutdown`], whether or not initialization or the tool call succeed.
+    pub async fn one_shot(
+        program: &str,
+        args: Vec<&str>,
+        envs: HashMap<String, String>,
+        tool_name: &str,
+        tool_args: serde_json::Value,
+    ) -> Result<CallToolResponse, ClientError> {
+        let mut client = Self::spawn(program, args, envs)?;
+        let result = match client.initialize(DEFAULT_PROTOCOL_VERSION).await {
+            Ok(_) => client.call_tool(tool_name, tool_args).await,
+            Err(e) => Err(e),
         };
+        let stopped = client.shutdown().await;
+
+        let response = result?;
+        stopped?;
+        Ok(response)
+    }
+
+    /// Create a [`ProtocolBuilder`] for configuring a client before spawning it
+    pub fn builder() -> ProtocolBuilder {
+        ProtocolBuilder::new()
+    }
+
+    /// Answer the server's `sampling/createMessage` requests with `handler`, replacing any handler
+    /// set before. An `Err` from the handler declines the request with that message. Without a
+    /// handler, sampling requests fail as unsupported. Requests asking for more tokens than
+    /// [`sampling_max_tokens`](Self::sampling_max_tokens) allowed when the handler was set are
+    /// declined without calling it. A result whose content isn't text or an image is sent back
+    /// as an error instead.
+    ///
+    /// Servers only send sampling requests to clients that advertised the `sampling`
+    /// capability, see [`ProtocolBuilder::capabilities`].
+    pub fn on_sampling_request<F, Fut>(&self, handler: F)
+    where
+        F: Fn(CreateMessageParams) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<CreateMessageResult, String>> + Send + 'static,
+    {
+        let max_tokens = self.sampling_max_tokens();
+        let handler: SamplingHandler = Arc::new(move |params| match max_tokens {
+            Some(limit) if params.max_tokens > limit => Box::pin(future::ready(Err(format!(
+                "Requested {} tokens, more than the limit of {}",
+                params.max_tokens, limit
+            )))),
+            _ => Box::pin(handler(params)),
+        });
+        *self.inner.sampling.lock().unwrap() = Some(handler);
+    }
+
+    /// Call `hook` with every message sent to or received from the server, exactly as written
+    /// or read, e.g. to log the wire traffic or record it for replay. Replaces any hook set
+    /// before. The hook runs inline on the sending and reading tasks, so it should be quick.
+    /// Use [`ProtocolBuilder::on_raw_message`] to also see the initialize handshake.
+    pub fn on_raw_message<F>(&self, hook: F)
+    where
+        F: Fn(Direction, &str) + Send + Sync + 'static,
+    {
+        self.set_raw_message_hook(Arc::new(hook));
+    }
+
+    pub(crate) fn set_raw_message_hook(&self, hook: RawMessageHook) {
+        *self.inner.raw_hook.lock().unwrap() = Some(hook);
+    }
+
+    /// Whether requests can currently reach the server. Only a server started with a
+    /// [restart policy](ProtocolBuilder::restart_policy) is ever
+    /// [`Reconnecting`](ConnectionState::Reconnecting).
+    pub fn connection_state(&self) -> ConnectionState {
+        *self.inner.state.borrow()
+    }
+
+    /// Watch the [connection state](Self::connection_state), e.g. to log restarts or stop
+    /// using a server that was given up on
+    pub fn watch_connection_state(&self) -> watch::Receiver<ConnectionState> {
+        self.inner.state.subscribe()
+    }
+
+    // Respawn the server with `respawn` whenever it exits, see `ProtocolBuilder::restart_policy`
+    pub(crate) fn set_restart_policy(&self, respawn: Respawn, backoff: ExponentialBackoff) {
+        *self.inner.restart.lock().unwrap() = Some(Restart {
+            respawn,
+            backoff,
+            next_id: self.next_id.clone(),
+            timeout: self.request_timeout,
+        });
+    }
+
+    /// Subscribe to notifications the server pushes, such as resource updates, list changes
+    /// and log messages. Only notifications arriving after the call are received; a subscriber
+    /// that falls more than 64 behind misses the oldest and gets
+    /// [`RecvError::Lagged`](tokio::sync::broadcast::error::RecvError::Lagged).
+    pub fn notifications(&self) -> broadcast::Receiver<ServerNotification> {
+        self.inner.notifications.subscribe()
+    }
+
+    /// Set the roots reported to the server when it sends `roots/list`, replacing any set
+    /// before. No roots are reported until this is called.
+    ///
+    /// If the client advertised roots with `listChanged` and is initialized, the server is sent
+    /// `notifications/roots/list_changed`.
+    pub async fn set_roots(&self, roots: Vec<Root>) -> Result<(), ClientError> {
+        *self.inner.roots.lock().unwrap() = roots;
+
+        let list_changed = self
+            .client_capabilities
+            .roots
+            .as_ref()
+            .is_some_and(|roots| roots.list_changed);
+        if list_changed && self.is_initialized() {
+            self.send_notification(RequestType::RootsListChanged, json!({}))
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Create another handle to the same server connection.
+    ///
+    /// Handles share the process, the in-flight request map and the request id counter, so a
+    /// response always reaches the handle that sent the request. Ids come from the shared
+    /// counter rather than a per-handle space because responses on one connection are told
+    /// apart only by id. Configuration (default meta, interceptors, retry policy, ...) and the
+    /// negotiated capabilities are copied; later changes to one handle don't affect the other.
+    /// The idle ping task stays with the original handle.
+    pub fn clone_handle(&self) -> Protocol {
+        Protocol {
+            inner: self.inner.clone(),
+            next_id: self.next_id.clone(),
+            client_info: self.client_info.clone(),
+            client_capabilities: self.client_capabilities.clone(),
+            capabilities: self.capabilities.clone(),
+            server_info: self.server_info.clone(),
+            protocol_version: self.protocol_version.clone(),
+            default_meta: self.default_meta.clone(),
+            method_support: std::sync::Mutex::new(self.method_support.lock().unwrap().clone()),
+            concurrency_limit: self.concurrency_limit.clone(),
+            request_timeout: self.request_timeout,
+            retry_policy: self.retry_policy.clone(),
+            interceptors: self.interceptors.clone(),
+            trust_methods_over_capabilities: self.trust_methods_over_capabilities,
+            idle_ping: None,
+            on_capabilities_change: self.on_capabilities_change.clone(),
+        }
+    }
+
+    /// Scope a sequence of calls to a single `deadline`. Every call made through the returned
+    /// handle gets only the time remaining until `deadline` and fails with
+    /// [`ClientError::Timeout`] once it has passed.
+    pub fn with_deadline(&self, deadline: Instant) -> Deadline<'_> {
+        Deadline::new(self, deadline)
+    }
 
-        let mut client = Self {
-            inner: Arc::new(Mutex::new(inner)),
-            next_id: AtomicU64::new(0),
+    // Spawn the server process without running the initialize handshake
+    pub(crate) fn spawn(
+        program: &str,
+        args: Vec<&str>,
+        envs: HashMap<String, String>,
+    ) -> Result<Self, ClientError> {
+        let transport = StdioTransport::spawn(program, args, envs)?;
+        let logs = transport.logs();
+        Ok(Self::from_transport(Arc::new(transport), logs))
+    }
+
+    // Set up a connection over `transport` without running the initialize handshake. Server
+    // logs are recorded in `logs`.
+    pub(crate) fn from_transport(transport: Arc<dyn Transport>, logs: Arc<LogBuffer>) -> Self {
+        let (inbound, inbound_requests) = mpsc::unbounded_channel();
+        let inner = Arc::new(Client {
+            transport: std::sync::RwLock::new(transport.clone()),
+            reader: std::sync::Mutex::new(None),
+            state: watch::channel(ConnectionState::Connected).0,
+            restart: std::sync::Mutex::new(None),
+            handshake: std::sync::Mutex::new(None),
+            in_flight: std::sync::Mutex::new(HashMap::new()),
+            last_activity: std::sync::Mutex::new(Instant::now()),
+            latency: std::sync::Mutex::new(None),
+            inbound,
+            server_request_slots: std::sync::Mutex::new(Arc::new(Semaphore::new(
+                DEFAULT_MAX_PENDING_SERVER_REQUESTS,
+            ))),
+            roots: std::sync::Mutex::new(vec![]),
+            subscriptions: std::sync::Mutex::new(BTreeSet::new()),
+            sampling: std::sync::Mutex::new(None),
+            raw_hook: std::sync::Mutex::new(None),
+            logs,
+            notifications: broadcast::channel(NOTIFICATION_CAPACITY).0,
+            #[cfg(feature = "lenient-json")]
+            lenient_json: AtomicBool::new(false),
+        });
+        let reader = tokio::spawn(read_loop(transport, Arc::downgrade(&inner)));
+        *inner.reader.lock().unwrap() = Some(reader);
+        tokio::spawn(inbound_loop(inbound_requests, Arc::downgrade(&inner)));
+
+        Self {
+            inner,
+            next_id: Arc::new(AtomicU64::new(0)),
+            client_info: ClientInfo {
+                name: env!("CARGO_PKG_NAME").to_string(),
+                version: env!("CARGO_PKG_VERSION").to_string(),
+            },
+            client_capabilities: ClientCapabilities::default(),
             capabilities: None,
+            server_info: None,
+            protocol_version: None,
+            default_meta: None,
+            method_support: std::sync::Mutex::new(None),
+            concurrency_limit: None,
+            request_timeout: DEFAULT_REQUEST_TIMEOUT,
+            retry_policy: None,
+            interceptors: vec![],
+            trust_methods_over_capabilities: false,
+            idle_ping: None,
+            on_capabilities_change: None,
+        }
+    }
+
+    /// Set the client name and version sent to the server during initialization. Defaults to
+    /// this crate's name and version.
+    pub fn set_client_info(&mut self, name: &str, version: &str) {
+        self.client_info = ClientInfo {
+            name: name.to_string(),
+            version: version.to_string(),
         };
+    }
 
-        client.initialize(version).await?;
+    /// Set the capabilities advertised to the server during initialization, e.g. roots. Takes
+    /// effect on the next [`Self::initialize`].
+    pub fn set_client_capabilities(&mut self, capabilities: ClientCapabilities) {
+        self.client_capabilities = capabilities;
+    }
 
-        Ok(client)
+    /// Set `_meta` entries that are merged into the params of every outbound request.
+    /// Keys already present in a request's own `_meta` (e.g. a progress token) take precedence.
+    pub fn set_default_meta(&mut self, meta: serde_json::Value) {
+        self.default_meta = Some(meta);
+    }
+
+    /// Allow at most `limit` requests to be awaiting a response at once. Additional requests
+    /// wait for a slot before being sent, which counts toward their timeout. Useful for servers
+    /// that rate-limit. A limit of 0 would block every request, so it is raised to 1.
+    pub fn set_max_in_flight(&mut self, limit: usize) {
+        self.concurrency_limit = Some(Arc::new(Semaphore::new(limit.max(1))));
+    }
+
+    /// Handle at most `limit` requests from the server (e.g. sampling) at once. Further requests
+    /// are answered right away with a `-32000` "Client is busy" error. Defaults to 32.
+    pub fn set_max_pending_server_requests(&mut self, limit: usize) {
+        *self.inner.server_request_slots.lock().unwrap() = Arc::new(Semaphore::new(limit));
+    }
+
+    /// Fail requests with [`ClientError::Timeout`] when the server hasn't answered within
+    /// `timeout`. Defaults to [`DEFAULT_REQUEST_TIMEOUT`].
+    pub fn set_request_timeout(&mut self, timeout: Duration) {
+        self.request_timeout = timeout;
     }
+
+    /// Set the policy used to retry failed tool calls. Tool calls are not retried by default
+    /// since they may not be idempotent.
+    pub fn set_retry_policy(&mut self, policy: Arc<dyn RetryPolicy>) {
+        self.retry_policy = Some(policy);
+    }
+
+    /// Register middleware that sees every outbound request and inbound response.
+    /// Interceptors run in the order they were added.
+    pub fn add_interceptor(&mut self, interceptor: Arc<dyn Interceptor>) {
+        self.interceptors.push(interceptor);
+    }
+
+    /// When enabled, methods are sent even if the server did not advertise the matching
+    /// capability, and the server's response (including method-not-found) is authoritative.
+    /// Useful for servers that under-report their capabilities.
+    pub fn set_trust_methods_over_capabilities(&mut self, trust: bool) {
+        self.trust_methods_over_capabilities = trust;
+    }
+
+    /// Send a `ping` whenever no traffic has been received from the server for `interval`.
+    /// Unlike a fixed heartbeat, busy connections are never pinged. A ping that goes
+    /// unanswered for the [request timeout](Self::set_request_timeout) closes the connection,
+    /// stopping a stdio server. Replaces any previously configured interval.
+    pub fn set_idle_ping_interval(&mut self, interval: Duration) {
+        if let Some(task) = self.idle_ping.take() {
+            task.abort();
+        }
+        self.idle_ping = Some(tokio::spawn(idle_ping_loop(
+            Arc::downgrade(&self.inner),
+            self.next_id.clone(),
+            interval,
+            self.request_timeout,
+        )));
+    }
+
+    /// Up to `limit` of the most recent server log entries at or above `min_level`, oldest
+    /// first. Covers the server's stderr output and the log messages it sends as
+    /// `notifications/message`. The last 1000 of each are kept, so log notifications never push
+    /// out the stderr lines reported in [`ClientError::ServerClosed`].
+    pub fn recent_logs(&self, min_level: LogLevel, limit: usize) -> Vec<LogEntry> {
+        self.inner.logs.recent(min_level, limit)
+    }
+
+    /// Register a callback invoked whenever the stored [`ServerCapabilities`] are replaced,
+    /// i.e. on every successful [`Self::initialize`], with the previous snapshot (`None` the
+    /// first time) and the new one. Replaces any previously registered callback.
+    pub fn on_capabilities_change<F>(&mut self, callback: F)
+    where
+        F: Fn(Option<&ServerCapabilities>, &ServerCapabilities) + Send + Sync + 'static,
+    {
+        self.on_capabilities_change = Some(Arc::new(callback));
+    }
+
+    pub(crate) fn set_capabilities_callback(&mut self, callback: CapabilitiesCallback) {
+        self.on_capabilities_change = Some(callback);
+    }
+
+    /// Accept technically invalid JSON (comments, trailing commas) in responses by falling back
+    /// to a JSON5 parser when strict parsing fails
+    #[cfg(feature = "lenient-json")]
+    pub fn set_lenient_json(&mut self, lenient: bool) {
+        self.inner.lenient_json.store(lenient, Ordering::Relaxed);
+    }
+
+    /// List requests that are still awaiting a response, with how long each has been pending.
+    /// Sorted by request id.
+    pub fn in_flight(&self) -> Vec<(u64, RequestType, Duration)> {
+        let in_flight = self.inner.in_flight.lock().unwrap();
+        let mut requests: Vec<_> = in_flight
+            .iter()
+            .map(|(id, (method, sent_at, _))| (*id, method.clone(), sent_at.elapsed()))
+            .collect();
+        requests.sort_by_key(|(id, _, _)| *id);
+        requests
+    }
+
     pub fn next_id(&self) -> u64 {
         self.next_id.fetch_add(1, Ordering::Relaxed)
     }
+    #[tracing::instrument(level = "debug", skip(self))]
     pub async fn initialize(&mut self, version: &str) -> Result<InitializeResponse, ClientError> {
         let init_params = InitializeParams {
             protocol_version: version.to_string(),
-            capabilities: serde_json::json!({}),
-            client_info: ClientInfo {
-                name: "test".to_string(),
-                version: "0.1.0".to_string(),
-            },
+            capabilities: self.client_capabilities.clone(),
+            client_info: self.client_info.clone(),
         };
 
+        let handshake = serde_json::to_value(&init_params)
+            .map_err(|e| ClientError::SerializationError(e.to_string()))?;
         let init_request =
             JsonRpcRequest::new(self.next_id(), RequestType::Initialize, init_params);
-        let response = self.send_request(init_request).await?;
+        let result = match self.send_request(init_request).await {
+            Ok(response) => response.into_result()?,
+            // Usually a server that failed to start; its stderr says why
+            Err(err @ ClientError::ServerClosed { .. }) => {
+                return Err(ClientError::InitializationFailed(err.to_string()))
+            }
+            Err(err) => return Err(err),
+        };
 
-        if let ResponseContent::Success { result } = response.response {
-            let init_response: InitializeResponse = serde_json::from_value(result)
-                .map_err(|e| ClientError::InitializationFailed(e.to_string()))?;
-            self.capabilities = Some(init_response.capabilities.clone());
-            Ok(init_response)
-        } else {
-            Err(ClientError::InitializationFailed(
-                "Initialization failed".to_string(),
-            ))
+        let init_response: InitializeResponse = serde_json::from_value(result)
+            .map_err(|e| ClientError::InitializationFailed(e.to_string()))?;
+        let previous = self
+            .capabilities
+            .replace(init_response.capabilities.clone());
+        if let Some(callback) = &self.on_capabilities_change {
+            callback(previous.as_ref(), &init_response.capabilities);
         }
+        self.server_info = Some(init_response.server_info.clone());
+        self.protocol_version = Some(init_response.protocol_version.clone());
+        *self.inner.handshake.lock().unwrap() = Some(handshake);
+
+        let advertised: Vec<ServerCapability> = ServerCapability::ALL
+            .into_iter()
+            .filter(|c| self.capable(*c))
+            .collect();
+        tracing::info!(
+            server_name = %init_response.server_info.name,
+            server_version = %init_response.server_info.version,
+            protocol_version = %init_response.protocol_version,
+            capabilities = ?advertised,
+            "Initialized MCP server"
+        );
+        // The server may reject other requests until it knows the handshake completed
+        self.send_notification(RequestType::Initialized, json!({}))
+            .await?;
+        Ok(init_response)
+    }
+
+    /// Whether the initialize handshake has completed successfully
+    pub fn is_initialized(&self) -> bool {
+        self.capabilities.is_some()
+    }
+
+    /// Server name and version reported during initialization
+    pub fn server_info(&self) -> Option<&ServerInfo> {
+        self.server_info.as_ref()
+    }
+
+    /// Protocol version the server agreed to during initialization
+    pub fn protocol_version(&self) -> Option<&str> {
+        self.protocol_version.as_deref()
+    }
+
+    /// Gather the cached initialization data plus tool and resource counts (for servers that
+    /// advertise them) into one profile. The counts cover every page of results, so this may
+    /// take several requests per list.
+    pub async fn describe(&self) -> Result<ServerProfile, ClientError> {
+        let (Some(server_info), Some(protocol_version), Some(capabilities)) = (
+            &self.server_info,
+            &self.protocol_version,
+            &self.capabilities,
+        ) else {
+            return Err(ClientError::NotInitialized);
+        };
+
+        let tool_count = if self.capable(ServerCapability::Tools) {
+            Some(self.list_all_tools().await?.len())
+        } else {
+            None
+        };
+        let resource_count = if self.capable(ServerCapability::Resources) {
+            Some(self.list_all_resources().await?.len())
+        } else {
+            None
+        };
+
+        Ok(ServerProfile {
+            name: server_info.name.clone(),
+            version: server_info.version.clone(),
+            protocol_version: protocol_version.clone(),
+            capabilities: capabilities.clone(),
+            tool_count,
+            resource_count,
+        })
     }
 
     /// Get the current server capabilities if initialized
@@ -99,48 +1128,405 @@ impl Protocol {
         self.capabilities.as_ref()
     }
 
+    /// Owned copy of the negotiated capabilities, e.g. to persist and reuse between runs
+    pub fn capabilities_snapshot(&self) -> Option<ServerCapabilities> {
+        self.capabilities.clone()
+    }
+
+    /// Methods listed in the server's sampling capability, if it advertises one
+    pub fn sampling_supported_methods(&self) -> Option<&[String]> {
+        self.capabilities
+            .as_ref()?
+            .sampling
+            .as_ref()
+            .map(|sampling| sampling.supported_methods.as_slice())
+    }
+
+    /// Token limit from the server's sampling capability, if it sets one
+    pub fn sampling_max_tokens(&self) -> Option<u32> {
+        self.capabilities.as_ref()?.sampling.as_ref()?.max_tokens
+    }
+
+    /// Send `request` and wait for the response. A JSON-RPC error from the server is returned
+    /// as [`ClientError::Rpc`], so a successful response always carries a result.
     pub async fn send_request<T: Serialize>(
         &self,
         request: JsonRpcRequest<T>,
     ) -> Result<JsonRpcResponse<serde_json::Value>, ClientError> {
-        let message = serde_json::to_string(&request)
+        self.send_request_with_timeout(request, self.request_timeout)
+            .await
+    }
+
+    #[tracing::instrument(
+        name = "mcp_request",
+        level = "debug",
+        skip_all,
+        fields(id = request.id(), method = request.method().as_str())
+    )]
+    async fn send_request_with_timeout<T: Serialize>(
+        &self,
+        request: JsonRpcRequest<T>,
+        timeout: Duration,
+    ) -> Result<JsonRpcResponse<serde_json::Value>, ClientError> {
+        let mut message = serde_json::to_value(&request)
+            .map_err(|e| ClientError::SerializationError(e.to_string()))?;
+        if let Some(meta) = &self.default_meta {
+            merge_default_meta(&mut message, meta);
+        }
+        for interceptor in &self.interceptors {
+            interceptor.on_request(&mut message);
+        }
+        let message = serde_json::to_string(&message)
             .map_err(|e| ClientError::SerializationError(e.to_string()))?;
-        let mut inner = self.inner.lock().await;
+        tracing::debug!(bytes = message.len(), "Sending request");
+        let started = Instant::now();
+        // Waiting for a slot counts toward the timeout. Dropping the exchange on timeout also
+        // removes the request from the in-flight map.
+        let exchange = async {
+            let _permit = match &self.concurrency_limit {
+                Some(limit) => Some(
+                    limit
+                        .acquire()
+                        .await
+                        .map_err(|_| self.inner.closed_error())?,
+                ),
+                None => None,
+            };
+            self.inner
+                .exchange(&message, request.id(), request.method().clone())
+                .await
+        };
+        let mut response = tokio::time::timeout(timeout, exchange)
+            .await
+            .map_err(|_| {
+                tracing::debug!(?timeout, "Request timed out");
+                ClientError::Timeout
+            })??;
+        tracing::debug!(elapsed = ?started.elapsed(), "Received response");
+        for interceptor in &self.interceptors {
+            interceptor.on_response(&mut response);
+        }
+        let response: JsonRpcResponse<serde_json::Value> = serde_json::from_value(response)
+            .map_err(|e| ClientError::ProtocolError(format!("Failed to parse response: {}", e)))?;
 
-        inner.stdin.write_all(message.as_bytes()).await?;
-        inner.stdin.write_all(b"\n").await?;
-        inner.stdin.flush().await?;
+        // Errors for requests the server couldn't parse come back with a null id
+        if response.id.is_none() {
+            return Err(match response.response {
+                ResponseContent::Error { error } => ClientError::ProtocolError(format!(
+                    "Server returned an error without a request id: {} (code {})",
+                    error.message, error.code
+                )),
+                ResponseContent::Success { .. } => {
+                    ClientError::ProtocolError("Server returned a response without an id".into())
+                }
+            });
+        }
+        if let ResponseContent::Error { error } = &response.response {
+            return Err(error.clone().into());
+        }
+        Ok(response)
+    }
 
-        let mut response = String::new();
-        inner.stdout.read_line(&mut response).await?;
+    /// Send a notification such as `notifications/cancelled`. Notifications have no id and get
+    /// no response, so this returns once the message is written.
+    pub async fn send_notification(
+        &self,
+        method: RequestType,
+        params: serde_json::Value,
+    ) -> Result<(), ClientError> {
+        let mut message = serde_json::to_value(JsonRpcNotification::new(method, params))
+            .map_err(|e| ClientError::SerializationError(e.to_string()))?;
+        for interceptor in &self.interceptors {
+            interceptor.on_request(&mut message);
+        }
+        let message = serde_json::to_string(&message)
+            .map_err(|e| ClientError::SerializationError(e.to_string()))?;
+        self.inner.write(&message).await
+    }
 
-        serde_json::from_str(&response)
-            .map_err(|e| ClientError::ProtocolError(format!("Failed to parse response: {}", e)))
+    /// Write `bytes` to the connection as-is, under the same lock as managed requests so
+    /// frames never interleave. Only supported by transports that implement
+    /// [`Transport::send_raw`], such as [`StdioTransport`].
+    ///
+    /// This is an escape hatch for testing malformed input or proxying. Nothing is added (not
+    /// even a newline), and any response the server sends to the raw bytes is not routed back
+    /// to the caller.
+    pub async fn write_raw(&self, bytes: &[u8]) -> Result<(), ClientError> {
+        self.inner.write_raw(bytes).await
+    }
+
+    /// Shut the server down gracefully. Active resource subscriptions are cancelled, then the
+    /// transport is closed. For stdio servers that closes the server's stdin so it can exit on
+    /// its own; it is killed if it is still running after [`SHUTDOWN_GRACE`](crate::SHUTDOWN_GRACE). Requests on any
+    /// handle to this connection fail with [`ClientError::ServerClosed`] afterwards.
+    ///
+    /// Dropping the last handle also stops a stdio server, but kills it immediately.
+    pub async fn shutdown(&self) -> Result<(), ClientError> {
+        for uri in self.active_subscriptions() {
+            if let Err(err) = self.unsubscribe_resource(&uri).await {
+                tracing::debug!(uri = %uri, error = %err, "Failed to unsubscribe during shutdown");
+            }
+        }
+
+        self.inner.state.send_replace(ConnectionState::Closed);
+        self.inner.transport().close().await
     }
 
     pub async fn call_tool(
         &self,
         name: &str,
         arguments: serde_json::Value,
+    ) -> Result<CallToolResponse, ClientError> {
+        self.call_tool_with_timeout(name, arguments, self.request_timeout)
+            .await
+    }
+
+    /// Call a tool, waiting up to `timeout` for each attempt instead of the client's request
+    /// timeout. Useful for tools known to be slow.
+    #[tracing::instrument(level = "debug", skip(self, arguments))]
+    pub async fn call_tool_with_timeout(
+        &self,
+        name: &str,
+        arguments: serde_json::Value,
+        timeout: Duration,
+    ) -> Result<CallToolResponse, ClientError> {
+        self.call_tool_retrying(name, arguments, None, timeout)
+            .await
+    }
+
+    /// Call a tool with `meta` as the request's `_meta`, e.g. a trace id for just this call. Its
+    /// keys take precedence over the builder's
+    /// [`default_meta`](crate::ProtocolBuilder::default_meta), whose other keys are still sent.
+    #[tracing::instrument(level = "debug", skip(self, arguments, meta))]
+    pub async fn call_tool_with_meta(
+        &self,
+        name: &str,
+        arguments: serde_json::Value,
+        meta: serde_json::Value,
+    ) -> Result<CallToolResponse, ClientError> {
+        self.call_tool_retrying(name, arguments, Some(meta), self.request_timeout)
+            .await
+    }
+
+    async fn call_tool_retrying(
+        &self,
+        name: &str,
+        arguments: serde_json::Value,
+        meta: Option<serde_json::Value>,
+        timeout: Duration,
     ) -> Result<CallToolResponse, ClientError> {
         self.check_capability(ServerCapability::Tools)?;
 
+        let mut attempt: u32 = 0;
+        loop {
+            let err = match self
+                .call_tool_once(name, arguments.clone(), meta.clone(), timeout)
+                .await
+            {
+                Ok(response) => return Ok(response),
+                Err(err) => err,
+            };
+
+            attempt = attempt.saturating_add(1);
+            match self
+                .retry_policy
+                .as_ref()
+                .and_then(|policy| policy.next_delay(attempt, &err))
+            {
+                Some(delay) => tokio::time::sleep(delay).await,
+                None => return Err(err),
+            }
+        }
+    }
+
+    /// Start a tool call that can be cancelled while it runs. Await the returned [`ToolCall`] for
+    /// the result; its [`ToolCall::id`] can be passed to [`Self::cancel`] from elsewhere. The
+    /// call is not retried.
+    pub fn call_tool_cancellable(&self, name: &str, arguments: serde_json::Value) -> ToolCall<'_> {
+        let id = self.next_id();
         let tool_params = ToolCallParams {
             name: name.to_string(),
             arguments,
+            meta: None,
+        };
+        let request = JsonRpcRequest::new(id, RequestType::CallTool, tool_params);
+        ToolCall {
+            id,
+            protocol: self,
+            response: Box::pin(async move {
+                self.check_capability(ServerCapability::Tools)?;
+                tool_result(self.send_request(request).await?)
+            }),
+        }
+    }
+
+    /// Cancel the in-flight request `request_id`: the server is sent `notifications/cancelled`
+    /// and the request fails with [`ClientError::Cancelled`] without waiting for the server.
+    /// Does nothing if the request already completed.
+    pub async fn cancel(&self, request_id: u64) -> Result<(), ClientError> {
+        let in_flight = self.inner.in_flight.lock().unwrap().remove(&request_id);
+        let Some((_, _, responder)) = in_flight else {
+            return Ok(());
+        };
+        let _ = responder.send(Err(ClientError::Cancelled));
+        self.send_notification(
+            RequestType::Cancelled,
+            json!({ "requestId": request_id, "reason": "Cancelled by the client" }),
+        )
+        .await
+    }
+
+    /// Call a tool with arguments given as a map, e.g. parsed from model output
+    pub async fn call_tool_map(
+        &self,
+        name: &str,
+        arguments: HashMap<String, serde_json::Value>,
+    ) -> Result<CallToolResponse, ClientError> {
+        self.call_tool(
+            name,
+            serde_json::Value::Object(arguments.into_iter().collect()),
+        )
+        .await
+    }
+
+    async fn call_tool_once(
+        &self,
+        name: &str,
+        arguments: serde_json::Value,
+        meta: Option<serde_json::Value>,
+        timeout: Duration,
+    ) -> Result<CallToolResponse, ClientError> {
+        let tool_params = ToolCallParams {
+            name: name.to_string(),
+            arguments,
+            meta,
         };
 
         let request = JsonRpcRequest::new(self.next_id(), RequestType::CallTool, tool_params);
-        let response = self.send_request(request).await?;
+        let response = self.send_request_with_timeout(request, timeout).await?;
+        tool_result(response)
+    }
 
-        if let ResponseContent::Success { result } = response.response {
-            serde_json::from_value(result).map_err(|e| {
-                ClientError::ToolError(format!("Failed to parse tool response: {}", e))
-            })
-        } else {
-            Err(ClientError::ToolError("Failed to call tool".to_string()))
+    /// Call a tool, passing each progress update the server reports for the call to
+    /// `on_progress` until the result arrives. The call is not retried.
+    pub async fn call_tool_with_progress<F>(
+        &self,
+        name: &str,
+        arguments: serde_json::Value,
+        on_progress: F,
+    ) -> Result<CallToolResponse, ClientError>
+    where
+        F: Fn(ProgressUpdate),
+    {
+        self.check_capability(ServerCapability::Tools)?;
+
+        // Request ids are unique per connection, so the id doubles as the progress token
+        let id = self.next_id();
+        let tool_params = ToolCallParams {
+            name: name.to_string(),
+            arguments,
+            meta: Some(json!({ "progressToken": id })),
+        };
+        let mut notifications = self.notifications();
+        let call = self.send_request(JsonRpcRequest::new(id, RequestType::CallTool, tool_params));
+        tokio::pin!(call);
+
+        let response = loop {
+            tokio::select! {
+                // Progress sent before the result is already queued once the result arrives,
+                // so draining notifications first delivers all of it
+                biased;
+                notification = notifications.recv() => match notification {
+                    Ok(ServerNotification::Progress {
+                        progress_token,
+                        progress,
+                        total,
+                        message,
+                    }) if progress_token == id => on_progress(ProgressUpdate {
+                        progress,
+                        total,
+                        message,
+                    }),
+                    Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => {}
+                    Err(broadcast::error::RecvError::Closed) => break (&mut call).await?,
+                },
+                response = &mut call => break response?,
+            }
+        };
+        tool_result(response)
+    }
+
+    /// Send a `ping` and return the measured round-trip time. Ping is part of the base protocol,
+    /// so no capability is required.
+    pub async fn ping(&self) -> Result<Duration, ClientError> {
+        let request = JsonRpcRequest::new(self.next_id(), RequestType::Ping, json!({}));
+        let started = Instant::now();
+        self.send_request(request).await?;
+        let elapsed = started.elapsed();
+        self.inner.record_latency(elapsed);
+        Ok(elapsed)
+    }
+
+    /// Check which standard MCP methods the server actually implements. Side-effect free
+    /// methods (listings, ping, completion) are sent with harmless params and count as
+    /// supported unless the server answers method-not-found. Methods that could change server
+    /// state (tool calls, subscriptions, log level) are judged by the advertised capabilities.
+    /// Results are cached; see [`Self::method_supported`].
+    pub async fn probe_methods(&self) -> HashMap<RequestType, bool> {
+        if let Some(cached) = self.method_support.lock().unwrap().clone() {
+            return cached;
         }
+
+        let mut support = HashMap::new();
+        for method in [
+            RequestType::Ping,
+            RequestType::ListTools,
+            RequestType::PromptsList,
+            RequestType::ResourcesList,
+            RequestType::ListResourceTemplates,
+            RequestType::CompletionComplete,
+        ] {
+            let request = JsonRpcRequest::new(self.next_id(), method.clone(), json!({}));
+            let supported = match self.send_request(request).await {
+                Ok(_) => true,
+                Err(ClientError::Rpc { code, .. }) => code != METHOD_NOT_FOUND,
+                Err(_) => false,
+            };
+            support.insert(method, supported);
+        }
+        for (method, capability) in [
+            (RequestType::CallTool, ServerCapability::Tools),
+            (RequestType::PromptsGet, ServerCapability::Prompts),
+            (RequestType::ResourcesRead, ServerCapability::Resources),
+            (RequestType::LoggingSetLevel, ServerCapability::Logging),
+        ] {
+            support.insert(method, self.capable(capability));
+        }
+        // Subscribing takes `resources.supports_subscribe` on top of the resources capability
+        let subscribe = self.check_subscribe_capability().is_ok();
+        support.insert(RequestType::ResourcesSubscribe, subscribe);
+        support.insert(RequestType::ResourcesUnsubscribe, subscribe);
+
+        *self.method_support.lock().unwrap() = Some(support.clone());
+        support
     }
+
+    /// Whether `method` was found to work by the last [`Self::probe_methods`], or `None` if it
+    /// wasn't probed
+    pub fn method_supported(&self, method: &RequestType) -> Option<bool> {
+        self.method_support
+            .lock()
+            .unwrap()
+            .as_ref()
+            .and_then(|support| support.get(method).copied())
+    }
+
+    /// Moving average of ping round-trip times, idle pings included, or `None` before the first
+    /// successful ping
+    pub fn latency(&self) -> Option<Duration> {
+        *self.inner.latency.lock().unwrap()
+    }
+
     pub fn capable(&self, capability: ServerCapability) -> bool {
         if let Some(caps) = &self.capabilities {
             match capability {
@@ -157,7 +1543,9 @@ impl Protocol {
     }
 
     fn check_capability(&self, capability: ServerCapability) -> Result<(), ClientError> {
-        if self.capable(capability) {
+        if !self.is_initialized() {
+            Err(ClientError::NotInitialized)
+        } else if self.trust_methods_over_capabilities || self.capable(capability) {
             Ok(())
         } else {
             Err(ClientError::CapabilityError(format!(
@@ -167,70 +1555,570 @@ impl Protocol {
         }
     }
 
+    /// The first page of the server's prompts, see [`list_all_prompts`](Self::list_all_prompts)
     pub async fn list_prompts(&self) -> Result<Vec<Prompt>, ClientError> {
+        Ok(self.prompts_page(None).await?.prompts)
+    }
+
+    /// Every prompt the server has, following `next_cursor` through all pages
+    pub async fn list_all_prompts(&self) -> Result<Vec<Prompt>, ClientError> {
+        collect_pages(|cursor| async move {
+            let page = self.prompts_page(cursor.as_deref()).await?;
+            Ok((page.prompts, page.next_cursor))
+        })
+        .await
+    }
+
+    async fn prompts_page(&self, cursor: Option<&str>) -> Result<ListPromptsResponse, ClientError> {
         self.check_capability(ServerCapability::Prompts)?;
-        let request = JsonRpcRequest::new(self.next_id(), RequestType::PromptsList, json!({}));
-        let response = self.send_request(request).await?;
-        if let ResponseContent::Success { result } = response.response {
-            serde_json::from_value(result).map_err(|e| {
-                ClientError::PromptError(format!("Failed to parse prompts list: {}", e))
-            })
-        } else {
-            Err(ClientError::PromptError(
-                "Failed to list prompts".to_string(),
-            ))
+        let request = JsonRpcRequest::new(
+            self.next_id(),
+            RequestType::PromptsList,
+            cursor_params(cursor),
+        );
+        let result = self.send_request(request).await?.into_result()?;
+        parse_result(result, "prompts list")
+    }
+
+    /// Render the prompt `name` with `arguments` into messages
+    pub async fn get_prompt(
+        &self,
+        name: &str,
+        arguments: serde_json::Value,
+    ) -> Result<PromptGetResponse, ClientError> {
+        self.check_capability(ServerCapability::Prompts)?;
+        let request = JsonRpcRequest::new(
+            self.next_id(),
+            RequestType::PromptsGet,
+            json!({ "name": name, "arguments": arguments }),
+        );
+        let result = self.send_request(request).await?.into_result()?;
+        parse_result(result, "prompt")
+    }
+
+    /// Render the prompt `name` like [`get_prompt`](Self::get_prompt), yielding each message as
+    /// the server streams it when it advertises `prompts.supports_streaming`. Other servers get a
+    /// single `prompts/get` whose messages are yielded once it returns. A failure ends the stream.
+    pub fn get_prompt_stream<'a>(
+        &'a self,
+        name: &'a str,
+        arguments: serde_json::Value,
+    ) -> impl Stream<Item = Result<PromptMessage, ClientError>> + 'a {
+        let streaming = self
+            .capabilities
+            .as_ref()
+            .and_then(|caps| caps.prompts.as_ref())
+            .is_some_and(|prompts| prompts.supports_streaming);
+        if !streaming {
+            return stream::once(self.get_prompt(name, arguments))
+                .flat_map(|result| stream::iter(prompt_messages(result, 0)))
+                .left_stream();
         }
+
+        // Request ids are unique per connection, so the id doubles as the progress token
+        let id = self.next_id();
+        let request = JsonRpcRequest::new(
+            id,
+            RequestType::PromptsGet,
+            json!({ "name": name, "arguments": arguments, "_meta": { "progressToken": id } }),
+        );
+        let state = PromptStream::Streaming {
+            notifications: self.notifications(),
+            call: Box::pin(self.send_request(request)),
+            streamed: 0,
+        };
+        stream::unfold(state, move |state| async move {
+            let mut rest = match state {
+                PromptStream::Streaming {
+                    mut notifications,
+                    mut call,
+                    streamed,
+                } => {
+                    let response = loop {
+                        tokio::select! {
+                            // Messages sent before the response are already queued once it
+                            // arrives, so draining notifications first yields all of them
+                            biased;
+                            notification = notifications.recv() => match notification {
+                                Ok(ServerNotification::PromptMessage {
+                                    progress_token,
+                                    message,
+                                }) if progress_token == id => {
+                                    let state = PromptStream::Streaming {
+                                        notifications,
+                                        call,
+                                        streamed: streamed + 1,
+                                    };
+                                    return Some((Ok(message), state));
+                                }
+                                Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => {}
+                                Err(broadcast::error::RecvError::Closed) => break (&mut call).await,
+                            },
+                            response = &mut call => break response,
+                        }
+                    };
+                    let result = response
+                        .and_then(JsonRpcResponse::into_result)
+                        .and_then(|result| parse_result(result, "prompt"));
+                    prompt_messages(result, streamed)
+                }
+                PromptStream::Rest(rest) => rest,
+            };
+            rest.next().map(|item| (item, PromptStream::Rest(rest)))
+        })
+        .right_stream()
     }
 
-    pub async fn list_resources(&self) -> Result<ResourcesListResponse, ClientError> {
+    /// One page of the server's resources: the first, or the one after `cursor` (a previous
+    /// page's `next_cursor`). `next_cursor` is set if there are more, see
+    /// [`list_all_resources`](Self::list_all_resources) to fetch them all.
+    pub async fn list_resources(
+        &self,
+        cursor: Option<String>,
+    ) -> Result<ResourcesListResponse, ClientError> {
         self.check_capability(ServerCapability::Resources)?;
-        let request = JsonRpcRequest::new(self.next_id(), RequestType::ResourcesList, json!({}));
-        let response = self.send_request(request).await?;
-        if let ResponseContent::Success { result } = response.response {
-            serde_json::from_value(result).map_err(|e| {
-                ClientError::ResourceError(format!("Failed to parse resources list: {}", e))
-            })
-        } else {
-            Err(ClientError::ResourceError(
-                "Failed to list resources".to_string(),
-            ))
-        }
+        let request = JsonRpcRequest::new(
+            self.next_id(),
+            RequestType::ResourcesList,
+            cursor_params(cursor.as_deref()),
+        );
+        let result = self.send_request(request).await?.into_result()?;
+        parse_result(result, "resources list")
+    }
+
+    /// Every resource the server has, following `next_cursor` through all pages
+    pub async fn list_all_resources(&self) -> Result<Vec<Resource>, ClientError> {
+        collect_pages(|cursor| async move {
+            let page = self.list_resources(cursor).await?;
+            Ok((page.resources, page.next_cursor))
+        })
+        .await
+    }
+
+    pub async fn list_resource_templates(&self) -> Result<ResourceTemplatesResponse, ClientError> {
+        self.check_capability(ServerCapability::Resources)?;
+        let request = JsonRpcRequest::new(
+            self.next_id(),
+            RequestType::ListResourceTemplates,
+            json!({}),
+        );
+        let result = self.send_request(request).await?.into_result()?;
+        parse_result(result, "resource templates list")
     }
 
+    pub async fn read_resource(&self, uri: &str) -> Result<ResourcesReadResponse, ClientError> {
+        self.check_capability(ServerCapability::Resources)?;
+        let request = JsonRpcRequest::new(
+            self.next_id(),
+            RequestType::ResourcesRead,
+            json!({ "uri": uri }),
+        );
+        let result = self.send_request(request).await?.into_result()?;
+        parse_result(result, "read resource response")
+    }
+
+    /// Read several resources one after another and combine their contents into a single
+    /// response. Fails on the first uri that can't be read; see
+    /// [`Self::read_resources_concurrent`] for per-uri results.
     pub async fn read_resources(
         &self,
         uris: Vec<String>,
     ) -> Result<ResourcesReadResponse, ClientError> {
-        self.check_capability(ServerCapability::Resources)?;
+        let mut combined = ResourcesReadResponse {
+            contents: vec![],
+            meta: None,
+        };
+        for uri in uris {
+            let response = self.read_resource(&uri).await?;
+            combined.contents.extend(response.contents);
+        }
+        Ok(combined)
+    }
+
+    /// Suggest values for argument `argument_name` given what the user has typed so far.
+    /// `reference` identifies the prompt or resource template, e.g.
+    /// `{"type": "ref/prompt", "name": "code_review"}`.
+    pub async fn complete(
+        &self,
+        reference: serde_json::Value,
+        argument_name: &str,
+        partial: &str,
+    ) -> Result<CompletionResponse, ClientError> {
+        if !self.is_initialized() {
+            return Err(ClientError::NotInitialized);
+        }
         let request = JsonRpcRequest::new(
             self.next_id(),
-            RequestType::ResourcesRead,
-            json!({ "uris": uris }),
+            RequestType::CompletionComplete,
+            json!({
+                "ref": reference,
+                "argument": { "name": argument_name, "value": partial },
+            }),
         );
-        let response = self.send_request(request).await?;
-        if let ResponseContent::Success { result } = response.response {
-            serde_json::from_value(result).map_err(|e| {
-                ClientError::ResourceError(format!(
-                    "Failed to parse read resources response: {}",
-                    e
-                ))
-            })
+        let result = self.send_request(request).await?.into_result()?;
+        let result: CompleteResult = parse_result(result, "completion")?;
+        Ok(result.completion)
+    }
+
+    /// Change how verbose the server's logging is. `level` must be one of the levels the server
+    /// advertised in its logging capability.
+    pub async fn set_log_level(&self, level: &str) -> Result<(), ClientError> {
+        self.check_capability(ServerCapability::Logging)?;
+        if let Some(logging) = self.capabilities.as_ref().and_then(|c| c.logging.as_ref()) {
+            if !logging.levels.iter().any(|l| l == level) {
+                return Err(ClientError::CapabilityError(format!(
+                    "Log level '{}' is not supported; server accepts: {}",
+                    level,
+                    logging.levels.join(", ")
+                )));
+            }
+        }
+        let request = JsonRpcRequest::new(
+            self.next_id(),
+            RequestType::LoggingSetLevel,
+            json!({ "level": level }),
+        );
+        self.send_request(request).await?;
+        Ok(())
+    }
+
+    /// Ask the server to send `notifications/resources/updated` when `uri` changes. Requires
+    /// the server to advertise `supports_subscribe`.
+    pub async fn subscribe_resource(&self, uri: &str) -> Result<(), ClientError> {
+        self.check_subscribe_capability()?;
+        let request = JsonRpcRequest::new(
+            self.next_id(),
+            RequestType::ResourcesSubscribe,
+            json!({ "uri": uri }),
+        );
+        self.send_request(request).await?;
+        self.inner
+            .subscriptions
+            .lock()
+            .unwrap()
+            .insert(uri.to_string());
+        Ok(())
+    }
+
+    /// Stop update notifications for `uri`. Fails without contacting the server if `uri` isn't
+    /// currently subscribed.
+    pub async fn unsubscribe_resource(&self, uri: &str) -> Result<(), ClientError> {
+        self.check_subscribe_capability()?;
+        if !self.inner.subscriptions.lock().unwrap().contains(uri) {
+            return Err(ClientError::ResourceError(format!(
+                "Not subscribed to {}",
+                uri
+            )));
+        }
+        let request = JsonRpcRequest::new(
+            self.next_id(),
+            RequestType::ResourcesUnsubscribe,
+            json!({ "uri": uri }),
+        );
+        self.send_request(request).await?;
+        self.inner.subscriptions.lock().unwrap().remove(uri);
+        Ok(())
+    }
+
+    /// Uris currently subscribed on this connection, sorted
+    pub fn active_subscriptions(&self) -> Vec<String> {
+        self.inner
+            .subscriptions
+            .lock()
+            .unwrap()
+            .iter()
+            .cloned()
+            .collect()
+    }
+
+    // Subscriptions need `resources.supports_subscribe`, not just the resources capability
+    fn check_subscribe_capability(&self) -> Result<(), ClientError> {
+        self.check_capability(ServerCapability::Resources)?;
+        let supported = self
+            .capabilities
+            .as_ref()
+            .and_then(|caps| caps.resources.as_ref())
+            .is_some_and(|resources| resources.supports_subscribe);
+        if supported || self.trust_methods_over_capabilities {
+            Ok(())
         } else {
-            Err(ClientError::ResourceError(
-                "Failed to read resources".to_string(),
+            Err(ClientError::CapabilityError(
+                "Server does not support resource subscriptions".to_string(),
             ))
         }
     }
 
+    /// Read each uri with its own request, running at most `max_concurrency` reads at a time
+    /// (default 4). Results are returned per uri in input order, so one failing uri does not
+    /// affect the others.
+    pub async fn read_resources_concurrent(
+        &self,
+        uris: Vec<String>,
+        max_concurrency: Option<usize>,
+    ) -> Vec<(String, Result<ResourcesReadResponse, ClientError>)> {
+        let max_concurrency = max_concurrency.unwrap_or(DEFAULT_READ_CONCURRENCY).max(1);
+        // A slow read shouldn't hold back the ones queued behind it, so reads finish in any
+        // order and are put back in input order at the end
+        let mut results: Vec<_> = stream::iter(uris.into_iter().enumerate())
+            .map(|(index, uri)| async move {
+                let result = self.read_resource(&uri).await;
+                (index, uri, result)
+            })
+            .buffer_unordered(max_concurrency)
+            .collect()
+            .await;
+        results.sort_by_key(|(index, _, _)| *index);
+        results
+            .into_iter()
+            .map(|(_, uri, result)| (uri, result))
+            .collect()
+    }
+
+    /// List the server's tools, keeping only those matching `predicate`, e.g. to expose only
+    /// read-only tools to a model with a tight token budget
+    pub async fn list_tools_where(
+        &self,
+        predicate: impl Fn(&Tool) -> bool,
+    ) -> Result<Vec<Tool>, ClientError> {
+        let tools = self.list_all_tools().await?;
+        Ok(tools.into_iter().filter(|tool| predicate(tool)).collect())
+    }
+
+    /// The first page of the server's tools. `next_cursor` is set if there are more, see
+    /// [`list_all_tools`](Self::list_all_tools).
     pub async fn list_tools(&self) -> Result<ListToolsResponse, ClientError> {
+        self.tools_page(None).await
+    }
+
+    /// Every tool the server has, following `next_cursor` through all pages
+    pub async fn list_all_tools(&self) -> Result<Vec<Tool>, ClientError> {
+        collect_pages(|cursor| async move {
+            let page = self.tools_page(cursor.as_deref()).await?;
+            Ok((page.tools, page.next_cursor))
+        })
+        .await
+    }
+
+    async fn tools_page(&self, cursor: Option<&str>) -> Result<ListToolsResponse, ClientError> {
         self.check_capability(ServerCapability::Tools)?;
-        let request = JsonRpcRequest::new(self.next_id(), RequestType::ListTools, json!({}));
-        let response = self.send_request(request).await?;
-        if let ResponseContent::Success { result } = response.response {
-            serde_json::from_value(result)
-                .map_err(|e| ClientError::ToolError(format!("Failed to parse tools list: {}", e)))
-        } else {
-            Err(ClientError::ToolError("Failed to list tools".to_string()))
+        let request = JsonRpcRequest::new(
+            self.next_id(),
+            RequestType::ListTools,
+            cursor_params(cursor),
+        );
+        let result = self.send_request(request).await?.into_result()?;
+        parse_result(result, "tools list")
+    }
+}
+
+/// A tool call started with [`Protocol::call_tool_cancellable`]. Resolves to the call's result
+/// when awaited.
+pub struct ToolCall<'a> {
+    id: u64,
+    protocol: &'a Protocol,
+    response: BoxFuture<'a, Result<CallToolResponse, ClientError>>,
+}
+
+impl ToolCall<'_> {
+    /// Id of the request, for [`Protocol::cancel`]
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Cancel the call; awaiting it then fails with [`ClientError::Cancelled`]
+    pub async fn cancel(&self) -> Result<(), ClientError> {
+        self.protocol.cancel(self.id).await
+    }
+}
+
+impl Future for ToolCall<'_> {
+    type Output = Result<CallToolResponse, ClientError>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.response.as_mut().poll(cx)
+    }
+}
+
+impl Drop for Protocol {
+    // The idle ping task would otherwise outlive the handle that configured it
+    fn drop(&mut self) {
+        if let Some(task) = self.idle_ping.take() {
+            task.abort();
+        }
+    }
+}
+
+// Ping the server whenever nothing has been read from it for `interval`. Exits once the
+// protocol is dropped or a ping fails; a ping unanswered within `timeout` means the server is
+// hung, so the connection is closed too.
+async fn idle_ping_loop(
+    inner: Weak<Client>,
+    next_id: Arc<AtomicU64>,
+    interval: Duration,
+    timeout: Duration,
+) {
+    loop {
+        let Some(inner) = inner.upgrade() else {
+            return;
+        };
+        let idle_for = inner.last_activity.lock().unwrap().elapsed();
+        if idle_for < interval {
+            drop(inner);
+            tokio::time::sleep(interval - idle_for).await;
+            continue;
+        }
+
+        let id = next_id.fetch_add(1, Ordering::Relaxed);
+        let request = JsonRpcRequest::new(id, RequestType::Ping, json!({}));
+        let Ok(message) = serde_json::to_string(&request) else {
+            return;
+        };
+
+        let started = Instant::now();
+        let pinged =
+            tokio::time::timeout(timeout, inner.exchange(&message, id, RequestType::Ping)).await;
+        match pinged {
+            Ok(Ok(_)) => inner.record_latency(started.elapsed()),
+            Ok(Err(e)) => {
+                tracing::warn!(error = %e, "Idle ping failed, stopping keepalive");
+                return;
+            }
+            Err(_) => {
+                tracing::warn!(?timeout, "Idle ping timed out, closing the connection");
+                inner.close();
+                if let Err(e) = inner.transport().close().await {
+                    tracing::debug!(error = %e, "Failed to close the transport");
+                }
+                return;
+            }
+        }
+    }
+}
+
+// Tracks a request in the in-flight map for as long as the guard lives, so entries are removed
+// even if the request errors or its future is dropped
+struct InFlightGuard<'a> {
+    in_flight: &'a InFlightMap,
+    id: u64,
+}
+
+impl<'a> InFlightGuard<'a> {
+    fn register(
+        in_flight: &'a InFlightMap,
+        id: u64,
+        method: RequestType,
+        responder: Responder,
+    ) -> Self {
+        in_flight
+            .lock()
+            .unwrap()
+            .insert(id, (method, Instant::now(), responder));
+        Self { in_flight, id }
+    }
+}
+
+impl Drop for InFlightGuard<'_> {
+    fn drop(&mut self) {
+        self.in_flight.lock().unwrap().remove(&self.id);
+    }
+}
+
+fn tool_result(
+    response: JsonRpcResponse<serde_json::Value>,
+) -> Result<CallToolResponse, ClientError> {
+    parse_result(response.into_result()?, "tool response")
+}
+
+// Where a streamed `get_prompt_stream` is up to: waiting on the server, or handing out what's
+// left once it has answered
+enum PromptStream<C> {
+    Streaming {
+        notifications: broadcast::Receiver<ServerNotification>,
+        call: Pin<Box<C>>,
+        streamed: usize,
+    },
+    Rest(std::vec::IntoIter<Result<PromptMessage, ClientError>>),
+}
+
+// The final `prompts/get` response lists every message, including the `streamed` ones already
+// yielded
+fn prompt_messages(
+    result: Result<PromptGetResponse, ClientError>,
+    streamed: usize,
+) -> std::vec::IntoIter<Result<PromptMessage, ClientError>> {
+    match result {
+        Ok(response) => response
+            .messages
+            .into_iter()
+            .skip(streamed)
+            .map(Ok)
+            .collect::<Vec<_>>()
+            .into_iter(),
+        Err(err) => vec![Err(err)].into_iter(),
+    }
+}
+
+// Deserialize a successful result, keeping the raw value in the error so callers can inspect
+// what the server actually sent
+fn parse_result<R: DeserializeOwned>(
+    result: serde_json::Value,
+    context: &str,
+) -> Result<R, ClientError> {
+    R::deserialize(&result).map_err(|e| ClientError::ResponseDeserialization {
+        message: format!("Failed to parse {}: {}", context, e),
+        raw: result,
+    })
+}
+
+// Params for a list request, asking for the page after `cursor` if given
+fn cursor_params(cursor: Option<&str>) -> serde_json::Value {
+    match cursor {
+        Some(cursor) => json!({ "cursor": cursor }),
+        None => json!({}),
+    }
+}
+
+// Fetch pages with `fetch`, passing each page's `next_cursor` to the next call, until a page
+// has none. Fails if the server hands out a cursor twice rather than looping forever.
+async fn collect_pages<T, F, Fut>(mut fetch: F) -> Result<Vec<T>, ClientError>
+where
+    F: FnMut(Option<String>) -> Fut,
+    Fut: Future<Output = Result<(Vec<T>, Option<String>), ClientError>>,
+{
+    let mut items = vec![];
+    let mut seen = BTreeSet::new();
+    let mut cursor = None;
+    loop {
+        let (page, next_cursor) = fetch(cursor).await?;
+        items.extend(page);
+        match next_cursor {
+            Some(next) if !seen.insert(next.clone()) => {
+                return Err(ClientError::ProtocolError(format!(
+                    "Server returned the page cursor '{}' twice",
+                    next
+                )))
+            }
+            Some(next) => cursor = Some(next),
+            None => return Ok(items),
+        }
+    }
+}
+
+// Merge `default` into `request.params._meta` without overwriting keys set by the request itself
+fn merge_default_meta(request: &mut serde_json::Value, default: &serde_json::Value) {
+    let (Some(params), Some(default)) = (
+        request.get_mut("params").and_then(|p| p.as_object_mut()),
+        default.as_object(),
+    ) else {
+        return;
+    };
+
+    let meta = params
+        .entry("_meta")
+        .or_insert_with(|| serde_json::Value::Object(serde_json::Map::new()));
+    if let Some(meta) = meta.as_object_mut() {
+        for (key, value) in default {
+            meta.entry(key.clone()).or_insert_with(|| value.clone());
         }
     }
 }
@@ -0,0 +1,394 @@
+use crate::protocol::{CapabilitiesCallback, RawMessageHook, Respawn};
+use crate::server_log::LogBuffer;
+use crate::types::{ClientCapabilities, Direction, ServerCapabilities};
+use crate::{
+    ClientError, ExponentialBackoff, Interceptor, Protocol, RetryPolicy, StdioTransport, Transport,
+};
+use std::{collections::HashMap, path::PathBuf, sync::Arc, time::Duration};
+
+/// Protocol version sent during initialization when none is configured
+pub const DEFAULT_PROTOCOL_VERSION: &str = "2024-11-05";
+
+/// Builder for spawning and initializing a [`Protocol`] client.
+///
+/// # Example
+/// ```no_run
+/// # async fn run() -> Result<(), mcp_client_rs::ClientError> {
+/// let client = mcp_client_rs::Protocol::builder()
+///     .program("npx")
+///     .args(vec!["-y", "@modelcontextprotocol/server-github"])
+///     .default_meta(serde_json::json!({ "traceId": "abc123" }))
+///     .build()
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct ProtocolBuilder {
+    version: String,
+    client_info: Option<(String, String)>,
+    capabilities: Option<ClientCapabilities>,
+    program: Option<String>,
+    transport: Option<Arc<dyn Transport>>,
+    args: Vec<String>,
+    envs: HashMap<String, String>,
+    // Parent variables passed to the server; `None` passes them all
+    inherited_env: Option<Vec<String>>,
+    current_dir: Option<PathBuf>,
+    default_meta: Option<serde_json::Value>,
+    retry_policy: Option<Arc<dyn RetryPolicy>>,
+    restart_policy: Option<ExponentialBackoff>,
+    skip_initialize: bool,
+    interceptors: Vec<Arc<dyn Interceptor>>,
+    trust_methods_over_capabilities: bool,
+    idle_ping_interval: Option<Duration>,
+    on_capabilities_change: Option<CapabilitiesCallback>,
+    on_raw_message: Option<RawMessageHook>,
+    max_in_flight: Option<usize>,
+    max_pending_server_requests: Option<usize>,
+    request_timeout: Option<Duration>,
+    #[cfg(feature = "lenient-json")]
+    lenient_json: bool,
+}
+
+impl Default for ProtocolBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ProtocolBuilder {
+    pub fn new() -> Self {
+        Self {
+            version: DEFAULT_PROTOCOL_VERSION.to_string(),
+            client_info: None,
+            capabilities: None,
+            program: None,
+            transport: None,
+            args: vec![],
+            envs: HashMap::new(),
+            inherited_env: None,
+            current_dir: None,
+            default_meta: None,
+            retry_policy: None,
+            restart_policy: None,
+            skip_initialize: false,
+            interceptors: vec![],
+            trust_methods_over_capabilities: false,
+            idle_ping_interval: None,
+            on_capabilities_change: None,
+            on_raw_message: None,
+            max_in_flight: None,
+            max_pending_server_requests: None,
+            request_timeout: None,
+            #[cfg(feature = "lenient-json")]
+            lenient_json: false,
+        }
+    }
+
+    /// Protocol version to request during initialization
+    pub fn version(mut self, version: &str) -> Self {
+        self.version = version.to_string();
+        self
+    }
+
+    /// Client name and version reported to the server during initialization. Defaults to this
+    /// crate's name and version.
+    pub fn client_info(mut self, name: &str, version: &str) -> Self {
+        self.client_info = Some((name.to_string(), version.to_string()));
+        self
+    }
+
+    /// Capabilities advertised to the server during initialization, e.g. roots. Nothing is
+    /// advertised by default.
+    pub fn capabilities(mut self, capabilities: ClientCapabilities) -> Self {
+        self.capabilities = Some(capabilities);
+        self
+    }
+
+    /// Program used to launch the server
+    pub fn program(mut self, program: &str) -> Self {
+        self.program = Some(program.to_string());
+        self
+    }
+
+    /// Connect over `transport` instead of spawning [`Self::program`]
+    pub fn transport(mut self, transport: impl Transport + 'static) -> Self {
+        self.transport = Some(Arc::new(transport));
+        self
+    }
+
+    /// Arguments passed to the server program
+    pub fn args(mut self, args: Vec<&str>) -> Self {
+        self.args = args.into_iter().map(String::from).collect();
+        self
+    }
+
+    /// Append one argument to those passed to the server program
+    pub fn arg(mut self, arg: &str) -> Self {
+        self.args.push(arg.to_string());
+        self
+    }
+
+    /// Environment variables set on the server process, replacing any set before. They are
+    /// layered over the inherited environment, see [`Self::inherit_env`].
+    pub fn envs(mut self, envs: HashMap<String, String>) -> Self {
+        self.envs = envs;
+        self
+    }
+
+    /// Set one environment variable on the server process
+    pub fn env(mut self, key: &str, value: &str) -> Self {
+        self.envs.insert(key.to_string(), value.to_string());
+        self
+    }
+
+    /// Whether the server process inherits this process's environment, on by default. When
+    /// off, it only sees the variables set with [`Self::env`] and [`Self::envs`].
+    pub fn inherit_env(mut self, inherit: bool) -> Self {
+        self.inherited_env = if inherit { None } else { Some(vec![]) };
+        self
+    }
+
+    /// Pass only the named variables (those that are set) from this process's environment to
+    /// the server, e.g. `PATH` and `HOME` for servers launched with `npx`. Variables set with
+    /// [`Self::env`] and [`Self::envs`] still take precedence.
+    pub fn inherit_env_vars(mut self, names: Vec<&str>) -> Self {
+        self.inherited_env = Some(names.into_iter().map(String::from).collect());
+        self
+    }
+
+    /// Working directory of the server process, instead of this process's
+    pub fn with_cwd(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.current_dir = Some(dir.into());
+        self
+    }
+
+    /// `_meta` entries merged into the params of every outbound request, e.g. a trace id.
+    /// Keys a request sets in its own `_meta`, such as those passed to
+    /// [`Protocol::call_tool_with_meta`], are never overwritten.
+    pub fn default_meta(mut self, meta: serde_json::Value) -> Self {
+        self.default_meta = Some(meta);
+        self
+    }
+
+    /// Policy used to retry failed tool calls, e.g. [`ExponentialBackoff`](crate::ExponentialBackoff)
+    pub fn retry_policy(mut self, policy: impl RetryPolicy + 'static) -> Self {
+        self.retry_policy = Some(Arc::new(policy));
+        self
+    }
+
+    /// Restart the server whenever it exits, waiting as `backoff` says between attempts and
+    /// closing the connection after `backoff.max_retries` failed attempts in a row. The new
+    /// server gets the initialize request the old one got and the same resource
+    /// subscriptions.
+    ///
+    /// Requests cut off by the exit are sent again, except tool calls, which fail with
+    /// [`ClientError::Disconnected`] since repeating them may not be safe. Requests made
+    /// during a restart wait for it to finish. See [`Protocol::connection_state`] to follow
+    /// restarts. Only servers started from [`Self::program`] can be restarted.
+    pub fn restart_policy(mut self, backoff: ExponentialBackoff) -> Self {
+        self.restart_policy = Some(backoff);
+        self
+    }
+
+    /// Add middleware that sees every request and response. Interceptors run in the order they
+    /// were added, including during the initialize handshake.
+    pub fn interceptor(mut self, interceptor: impl Interceptor + 'static) -> Self {
+        self.interceptors.push(Arc::new(interceptor));
+        self
+    }
+
+    /// Skip the capability pre-flight check and send methods regardless of what the server
+    /// advertised, for servers that under-report capabilities
+    pub fn trust_methods_over_capabilities(mut self, trust: bool) -> Self {
+        self.trust_methods_over_capabilities = trust;
+        self
+    }
+
+    /// Ping the server whenever it has been silent for `interval`, keeping NAT/proxy
+    /// connections alive without adding traffic to busy connections
+    pub fn idle_ping_interval(mut self, interval: Duration) -> Self {
+        self.idle_ping_interval = Some(interval);
+        self
+    }
+
+    /// Callback invoked with the old and new capabilities whenever they are replaced,
+    /// including by the initial handshake in [`Self::build`]
+    pub fn on_capabilities_change<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(Option<&ServerCapabilities>, &ServerCapabilities) + Send + Sync + 'static,
+    {
+        self.on_capabilities_change = Some(Arc::new(callback));
+        self
+    }
+
+    /// Hook called with every message sent to or received from the server, from the
+    /// initialize handshake on. See [`Protocol::on_raw_message`].
+    pub fn on_raw_message<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(Direction, &str) + Send + Sync + 'static,
+    {
+        self.on_raw_message = Some(Arc::new(hook));
+        self
+    }
+
+    /// Limit how many requests may be awaiting a response from this server at once. Further
+    /// requests wait for a slot before being sent, within their timeout. 0 is treated as 1.
+    pub fn max_in_flight(mut self, limit: usize) -> Self {
+        self.max_in_flight = Some(limit);
+        self
+    }
+
+    /// Limit how many requests from the server (e.g. sampling) are handled at once, 32 by
+    /// default. Each runs on its own task, so a slow handler never holds up responses. Requests
+    /// arriving while the limit is reached are not queued: the server is answered right away
+    /// with a `-32000` "Client is busy" error and may retry later.
+    pub fn max_pending_server_requests(mut self, limit: usize) -> Self {
+        self.max_pending_server_requests = Some(limit);
+        self
+    }
+
+    /// How long each request waits for its response before failing with
+    /// [`ClientError::Timeout`]. Defaults to [`DEFAULT_REQUEST_TIMEOUT`](crate::DEFAULT_REQUEST_TIMEOUT).
+    pub fn request_timeout(mut self, timeout: Duration) -> Self {
+        self.request_timeout = Some(timeout);
+        self
+    }
+
+    /// Parse responses leniently, accepting JSON5 such as comments and trailing commas.
+    /// Strict `serde_json` parsing is always tried first.
+    #[cfg(feature = "lenient-json")]
+    pub fn lenient_json(mut self, lenient: bool) -> Self {
+        self.lenient_json = lenient;
+        self
+    }
+
+    /// Spawn the server without running the initialize handshake, leaving it to the caller
+    /// via [`Protocol::initialize`]. Until then every capability-gated method fails with
+    /// [`ClientError::NotInitialized`].
+    pub fn skip_initialize(mut self) -> Self {
+        self.skip_initialize = true;
+        self
+    }
+
+    /// Spawn the server and run the initialize handshake unless [`Self::skip_initialize`] was set
+    pub async fn build(self) -> Result<Protocol, ClientError> {
+        let (mut client, command) = match (self.transport, self.program) {
+            (Some(_), _) if self.restart_policy.is_some() => {
+                return Err(ClientError::InitializationFailed(
+                    "Only servers started from a program can be restarted".to_string(),
+                ))
+            }
+            (Some(transport), _) => (
+                Protocol::from_transport(transport, Arc::new(LogBuffer::default())),
+                None,
+            ),
+            (None, Some(program)) => {
+                let command = ServerCommand {
+                    program,
+                    args: self.args,
+                    envs: self.envs,
+                    inherited_env: self.inherited_env,
+                    current_dir: self.current_dir,
+                };
+                let logs = Arc::new(LogBuffer::default());
+                let transport = command.spawn(logs.clone())?;
+                (
+                    Protocol::from_transport(Arc::new(transport), logs),
+                    Some(command),
+                )
+            }
+            (None, None) => {
+                return Err(ClientError::InitializationFailed(
+                    "No program or transport specified".to_string(),
+                ))
+            }
+        };
+        if let Some((name, version)) = &self.client_info {
+            client.set_client_info(name, version);
+        }
+        if let Some(capabilities) = self.capabilities {
+            client.set_client_capabilities(capabilities);
+        }
+        if let Some(meta) = self.default_meta {
+            client.set_default_meta(meta);
+        }
+        if let Some(policy) = self.retry_policy {
+            client.set_retry_policy(policy);
+        }
+        for interceptor in self.interceptors {
+            client.add_interceptor(interceptor);
+        }
+        client.set_trust_methods_over_capabilities(self.trust_methods_over_capabilities);
+        if let Some(limit) = self.max_in_flight {
+            client.set_max_in_flight(limit);
+        }
+        if let Some(limit) = self.max_pending_server_requests {
+            client.set_max_pending_server_requests(limit);
+        }
+        if let Some(timeout) = self.request_timeout {
+            client.set_request_timeout(timeout);
+        }
+        if let (Some(backoff), Some(command)) = (self.restart_policy, command) {
+            let respawn: Respawn = Arc::new(move |logs| {
+                let transport: Arc<dyn Transport> = Arc::new(command.spawn(logs)?);
+                Ok(transport)
+            });
+            client.set_restart_policy(respawn, backoff);
+        }
+        if let Some(callback) = self.on_capabilities_change {
+            client.set_capabilities_callback(callback);
+        }
+        if let Some(hook) = self.on_raw_message {
+            client.set_raw_message_hook(hook);
+        }
+        #[cfg(feature = "lenient-json")]
+        client.set_lenient_json(self.lenient_json);
+
+        if !self.skip_initialize {
+            client.initialize(&self.version).await?;
+        }
+        if let Some(interval) = self.idle_ping_interval {
+            client.set_idle_ping_interval(interval);
+        }
+
+        Ok(client)
+    }
+}
+
+// How to launch the server process, kept to launch it again on restart
+struct ServerCommand {
+    program: String,
+    args: Vec<String>,
+    envs: HashMap<String, String>,
+    // Parent variables passed to the server; `None` passes them all
+    inherited_env: Option<Vec<String>>,
+    current_dir: Option<PathBuf>,
+}
+
+impl ServerCommand {
+    fn spawn(&self, logs: Arc<LogBuffer>) -> Result<StdioTransport, ClientError> {
+        let mut command = tokio::process::Command::new(&self.program);
+        if let Some(names) = &self.inherited_env {
+            command.env_clear();
+            for name in names {
+                if let Some(value) = std::env::var_os(name) {
+                    command.env(name, value);
+                }
+            }
+        }
+        command.args(&self.args).envs(&self.envs);
+        if let Some(dir) = &self.current_dir {
+            // Spawning would fail with the same error as a missing program
+            if !dir.is_dir() {
+                return Err(ClientError::Io(std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    format!("Working directory {} does not exist", dir.display()),
+                )));
+            }
+            command.current_dir(dir);
+        }
+        StdioTransport::from_command(command, logs)
+    }
+}
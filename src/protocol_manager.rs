@@ -1,6 +1,10 @@
-use crate::{Protocol, Tool};
-use anyhow::Result;
-use std::collections::HashMap;
+use crate::{
+    CallToolResponse, ClientError, McpConfig, McpServerConfig, Prompt, PromptGetResponse, Protocol,
+    ServerCapability, Tool,
+};
+use futures::future::try_join_all;
+use serde_json::json;
+use std::{collections::HashMap, path::Path};
 
 // Change to work with references instead of owned values
 fn filter_tools_by_name<'a>(tools: &'a [Tool], tool_names: &[String]) -> Vec<&'a Tool> {
@@ -10,84 +14,250 @@ fn filter_tools_by_name<'a>(tools: &'a [Tool], tool_names: &[String]) -> Vec<&'a
         .collect()
 }
 
-// Work with references instead of owned values
-fn format_tools_for_prompt(tools: &[&Tool], starting_index: &usize) -> String {
+// Spawn and initialize the server `name`, returning it with its tools
+async fn start(name: &str, server: McpServerConfig) -> Result<(Protocol, Vec<Tool>), ClientError> {
+    let mut builder = Protocol::builder()
+        .program(&server.command)
+        .args(server.args.iter().map(String::as_str).collect())
+        .envs(server.env);
+    if let Some(limit) = server.max_in_flight {
+        builder = builder.max_in_flight(limit);
+    }
+    let client = builder
+        .build()
+        .await
+        .map_err(|e| ClientError::InitializationFailed(format!("Server '{}': {}", name, e)))?;
+    let tools = client.list_all_tools().await?;
+    Ok((client, tools))
+}
+
+// One line per tool, prefixed with its number
+fn format_tools_for_prompt<'a>(tools: impl Iterator<Item = (usize, &'a Tool)>) -> String {
     tools
-        .iter()
-        .enumerate()
-        .map(|(i, t)| format!("{}. {}: {}\n", i + starting_index, t.name, t.description))
+        .map(|(i, t)| format!("{}. {}: {}\n", i, t.name, t.description))
         .collect()
 }
 
+/// A tool or a prompt exposed by one of the managed servers, invoked uniformly with
+/// [`Callable::invoke`]
+pub enum Callable<'a> {
+    Tool {
+        client_id: &'a str,
+        client: &'a Protocol,
+        tool: &'a Tool,
+    },
+    Prompt {
+        client_id: &'a str,
+        client: &'a Protocol,
+        prompt: Prompt,
+    },
+}
+
+/// Output of [`Callable::invoke`]
+#[derive(Debug)]
+pub enum CallableOutput {
+    Tool(CallToolResponse),
+    Prompt(PromptGetResponse),
+}
+
+impl Callable<'_> {
+    pub fn name(&self) -> &str {
+        match self {
+            Callable::Tool { tool, .. } => &tool.name,
+            Callable::Prompt { prompt, .. } => &prompt.name,
+        }
+    }
+
+    pub fn client_id(&self) -> &str {
+        match self {
+            Callable::Tool { client_id, .. } | Callable::Prompt { client_id, .. } => client_id,
+        }
+    }
+
+    /// Call the tool, or get the prompt, with `arguments`
+    pub async fn invoke(
+        &self,
+        arguments: serde_json::Value,
+    ) -> Result<CallableOutput, ClientError> {
+        match self {
+            Callable::Tool { client, tool, .. } => client
+                .call_tool(&tool.name, arguments)
+                .await
+                .map(CallableOutput::Tool),
+            Callable::Prompt { client, prompt, .. } => client
+                .get_prompt(&prompt.name, arguments)
+                .await
+                .map(CallableOutput::Prompt),
+        }
+    }
+}
+
 pub struct ProtocolManager {
-    pub tool_counter: usize,
-    pub clients: Vec<Protocol>,
-    pub formatted_tools: Vec<String>,
+    pub clients: HashMap<String, Protocol>,
     pub client_tools: HashMap<String, Vec<Tool>>, // This still owns the Tools
+    // Keys of `clients` in registration order
+    order: Vec<String>,
+}
+
+impl Default for ProtocolManager {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl ProtocolManager {
     pub fn new() -> Self {
         Self {
-            tool_counter: 1,
-            clients: vec![],
-            formatted_tools: vec![],
+            clients: HashMap::new(),
             client_tools: HashMap::new(),
+            order: vec![],
         }
     }
 
+    /// Spawn `program` with `args` and `envs` and register it under `client_id`, keeping only
+    /// the tools in `tool_names` if given. `max_in_flight` caps how many requests the server
+    /// gets at once.
     pub async fn add_protocol(
         &mut self,
         client_id: &str,
-        command_args: Vec<&str>,
+        program: &str,
+        args: Vec<&str>,
+        envs: HashMap<String, String>,
+        max_in_flight: Option<usize>,
         tool_names: Option<Vec<String>>,
-    ) -> Result<()> {
-        let client = Protocol::new(
-            "0",
-            "npx",
-            command_args.iter().map(|&s| s).collect(),
-            std::collections::HashMap::new(),
-        )
+    ) -> Result<(), ClientError> {
+        let server = McpServerConfig {
+            command: program.to_string(),
+            args: args.into_iter().map(String::from).collect(),
+            env: envs,
+            max_in_flight,
+        };
+        let (client, tools) = start(client_id, server).await?;
+        self.register(client_id, client, tools, tool_names);
+
+        Ok(())
+    }
+
+    /// Spawn and initialize every server listed in an `mcpServers` JSON config file, keyed by
+    /// server name. Servers are started concurrently.
+    pub async fn from_config(path: impl AsRef<Path>) -> Result<Self, ClientError> {
+        let contents = tokio::fs::read_to_string(path).await?;
+        let config: McpConfig = serde_json::from_str(&contents)?;
+
+        // Sort by name so tool numbering is stable between runs
+        let mut servers: Vec<_> = config.mcp_servers.into_iter().collect();
+        servers.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        let started = try_join_all(servers.into_iter().map(|(name, server)| async move {
+            let (client, tools) = start(&name, server).await?;
+            Ok::<_, ClientError>((name, client, tools))
+        }))
         .await?;
 
-        let tools = client.list_tools().await?;
+        let mut manager = Self::new();
+        for (name, client, tools) in started {
+            manager.register(&name, client, tools, None);
+        }
+        Ok(manager)
+    }
 
-        // Here we need to clone because we're storing the tools
-        let filtered_tools = if let Some(names) = tool_names {
-            let refs: Vec<&Tool> = filter_tools_by_name(&tools.tools, &names);
-            refs.into_iter().cloned().collect()
-        } else {
-            tools.tools.clone()
+    // Store a client and its (optionally filtered) tools under `client_id`
+    fn register(
+        &mut self,
+        client_id: &str,
+        client: Protocol,
+        tools: Vec<Tool>,
+        tool_names: Option<Vec<String>>,
+    ) {
+        let filtered_tools = match tool_names {
+            Some(names) => filter_tools_by_name(&tools, &names)
+                .into_iter()
+                .cloned()
+                .collect(),
+            None => tools,
         };
 
-        // Format tools using references
-        let refs: Vec<&Tool> = filtered_tools.iter().collect();
-        self.formatted_tools
-            .push(format_tools_for_prompt(&refs, &self.tool_counter));
-
-        self.tool_counter += filtered_tools.len();
         self.client_tools
             .insert(client_id.to_string(), filtered_tools);
-        self.clients.push(client);
+        if self.clients.insert(client_id.to_string(), client).is_some() {
+            self.order.retain(|id| id != client_id);
+        }
+        self.order.push(client_id.to_string());
+    }
 
-        Ok(())
+    /// Shut down the server registered under `client_id` and forget it and its tools. The
+    /// remaining tools are renumbered, see [`Self::get_tools_for_clients`].
+    pub async fn remove_protocol(&mut self, client_id: &str) -> Result<(), ClientError> {
+        let Some(client) = self.clients.remove(client_id) else {
+            return Err(ClientError::ProtocolError(format!(
+                "No client registered as '{}'",
+                client_id
+            )));
+        };
+        self.client_tools.remove(client_id);
+        self.order.retain(|id| id != client_id);
+
+        client.shutdown().await
+    }
+
+    /// Every registered tool plus the prompts of servers advertising prompts, in registration
+    /// order. Prompts are fetched from the servers on each call.
+    pub async fn list_callables(&self) -> Result<Vec<Callable<'_>>, ClientError> {
+        let mut callables = vec![];
+        for (client_id, client) in self.registered() {
+            let tools = self.client_tools.get(client_id).into_iter().flatten();
+            callables.extend(tools.map(|tool| Callable::Tool {
+                client_id,
+                client,
+                tool,
+            }));
+
+            if client.capable(ServerCapability::Prompts) {
+                let prompts = client.list_all_prompts().await?;
+                callables.extend(prompts.into_iter().map(|prompt| Callable::Prompt {
+                    client_id,
+                    client,
+                    prompt,
+                }));
+            }
+        }
+        Ok(callables)
     }
 
+    /// Numbered list of the tools of `client_ids` (all clients if `None`) for a prompt. Tools
+    /// are numbered from 1 across all clients in registration order, so a tool keeps its
+    /// number whichever clients are listed.
     pub fn get_tools_for_clients(&self, client_ids: Option<&[String]>) -> String {
-        let tools: Vec<&Tool> = match client_ids {
-            Some(ids) => ids
-                .iter()
-                .filter_map(|id| self.client_tools.get(id))
-                .flat_map(|tools| tools.iter())
-                .collect(),
-            None => self
-                .client_tools
-                .values()
-                .flat_map(|tools| tools.iter())
-                .collect(),
-        };
+        format_tools_for_prompt(
+            self.numbered_tools()
+                .filter(|(_, client_id, _)| client_ids.is_none_or(|ids| ids.contains(client_id)))
+                .map(|(i, _, tool)| (i, tool)),
+        )
+    }
 
-        format_tools_for_prompt(&tools, &1)
+    // Every registered tool with its number and client id, in registration order
+    fn numbered_tools(&self) -> impl Iterator<Item = (usize, &String, &Tool)> {
+        self.order
+            .iter()
+            .filter_map(|client_id| Some((client_id, self.client_tools.get(client_id)?)))
+            .flat_map(|(client_id, tools)| tools.iter().map(move |tool| (client_id, tool)))
+            .enumerate()
+            .map(|(i, (client_id, tool))| (i + 1, client_id, tool))
+    }
+
+    /// Full tool catalog as a JSON array of `{ client_id, name, description, input_schema }`
+    /// objects, in registration order like [`Self::get_tools_for_clients`].
+    pub fn to_json_catalog(&self) -> serde_json::Value {
+        self.numbered_tools()
+            .map(|(_, client_id, tool)| {
+                json!({
+                    "client_id": client_id,
+                    "name": tool.name,
+                    "description": tool.description,
+                    "input_schema": tool.input_schema,
+                })
+            })
+            .collect()
     }
 
     /// Gets tool structs associated with specific client IDs or all tools if no IDs are specified.
@@ -105,13 +275,10 @@ impl ProtocolManager {
                 .filter_map(|id| self.client_tools.get(id))
                 .flat_map(|tools| tools.iter())
                 .collect(),
-            None => self
-                .client_tools
-                .values()
-                .flat_map(|tools| tools.iter())
-                .collect(),
+            None => self.numbered_tools().map(|(_, _, tool)| tool).collect(),
         }
     }
+
     /// Gets protocols associated with specific client IDs or all protocols if no IDs are specified.
     /// Returns references since the protocols need to stay in the ProtocolManager for later use.
     ///
@@ -121,32 +288,90 @@ impl ProtocolManager {
     /// # Returns
     /// * Vec of references to Protocol instances matching the filter criteria
     pub fn get_protocols<'a>(&'a self, client_ids: Option<&[String]>) -> Vec<&'a Protocol> {
-        match client_ids {
-            Some(ids) => self
-                .clients
-                .iter()
-                .enumerate()
-                .filter(|(_i, _)| ids.iter().any(|id| self.client_tools.contains_key(id)))
-                .map(|(_, protocol)| protocol)
-                .collect(),
-            None => self.clients.iter().collect(),
+        self.registered()
+            .filter(|(client_id, _)| client_ids.is_none_or(|ids| ids.contains(client_id)))
+            .map(|(_, protocol)| protocol)
+            .collect()
+    }
+
+    /// Call `tool_name` on the client that registered it. When several clients expose a tool
+    /// with that name, `client_id` picks one; without it the call fails as ambiguous.
+    pub async fn call_tool(
+        &self,
+        tool_name: &str,
+        arguments: serde_json::Value,
+        client_id: Option<&str>,
+    ) -> Result<CallToolResponse, ClientError> {
+        let owners: Vec<_> = self
+            .registered()
+            .filter(|(id, _)| client_id.is_none_or(|client_id| client_id == id.as_str()))
+            .filter(|(id, _)| {
+                self.client_tools
+                    .get(*id)
+                    .is_some_and(|tools| tools.iter().any(|tool| tool.name == tool_name))
+            })
+            .collect();
+
+        match owners.as_slice() {
+            [(_, client)] => client.call_tool(tool_name, arguments).await,
+            [] => Err(ClientError::ToolError(match client_id {
+                Some(client_id) => format!(
+                    "Client '{}' does not provide tool '{}'",
+                    client_id, tool_name
+                ),
+                None => format!("No client provides tool '{}'", tool_name),
+            })),
+            _ => Err(ClientError::ToolError(format!(
+                "Tool '{}' is provided by several clients ({}); pass a client id",
+                tool_name,
+                owners
+                    .iter()
+                    .map(|(id, _)| id.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ))),
         }
     }
+
+    /// The protocol registered under `client_id`
+    pub fn get_protocol(&self, client_id: &str) -> Option<&Protocol> {
+        self.clients.get(client_id)
+    }
+
+    // Registered clients with their ids, in registration order. Skips ids removed from the
+    // public `clients` map directly.
+    fn registered(&self) -> impl Iterator<Item = (&String, &Protocol)> {
+        self.order
+            .iter()
+            .filter_map(|id| Some((id, self.clients.get(id)?)))
+    }
 }
 // Usage:
 //  let mut manager = ProtocolManager::new();
 
 //  manager.add_protocol(
+//     "github",
+//     "npx",
 //     vec!["-y", "@modelcontextprotocol/server-github"],
+//     HashMap::from([("GITHUB_PERSONAL_ACCESS_TOKEN".to_string(), token)]),
+//     Some(2),
 //     Some(vec!["search_repositories".to_string(), "get_file_contents".to_string()])
 //  ).await?;
 
 //  manager.add_protocol(
+//     "filesystem",
+//     "npx",
 //     vec!["-y", "@modelcontextprotocol/server-filesystem", project_path],
+//     HashMap::new(),
+//     None,
 //     None
 //  ).await?;
 
 //  manager.add_protocol(
-//     vec!["-y", "@modelcontextprotocol/server-sequential-thinking"],
+//     "fetch",
+//     "uvx",
+//     vec!["mcp-server-fetch"],
+//     HashMap::new(),
+//     None,
 //     None
 //  ).await?;
@@ -0,0 +1,78 @@
+use crate::ClientError;
+use std::time::Duration;
+
+/// Decides whether and when a failed request is retried
+pub trait RetryPolicy: Send + Sync {
+    /// Delay before retry number `attempt` (starting at 1) after `err`, or `None` to give up
+    fn next_delay(&self, attempt: u32, err: &ClientError) -> Option<Duration>;
+}
+
+/// Retries I/O failures and timeouts with exponentially growing delays
+#[derive(Debug, Clone)]
+pub struct ExponentialBackoff {
+    pub initial_delay: Duration,
+    pub max_delay: Duration,
+    pub multiplier: f64,
+    pub max_retries: u32,
+}
+
+impl Default for ExponentialBackoff {
+    fn default() -> Self {
+        Self {
+            initial_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(10),
+            multiplier: 2.0,
+            max_retries: 3,
+        }
+    }
+}
+
+impl ExponentialBackoff {
+    /// Backoff starting at `initial_delay` and growing by `multiplier` each attempt, up to
+    /// `max_delay`, for at most `max_retries` attempts.
+    ///
+    /// # Panics
+    ///
+    /// If `multiplier` is below 1 or not finite
+    pub fn new(
+        initial_delay: Duration,
+        max_delay: Duration,
+        multiplier: f64,
+        max_retries: u32,
+    ) -> Self {
+        assert!(
+            multiplier.is_finite() && multiplier >= 1.0,
+            "backoff multiplier must be finite and at least 1, got {}",
+            multiplier
+        );
+        Self {
+            initial_delay,
+            max_delay,
+            multiplier,
+            max_retries,
+        }
+    }
+
+    // Delay before attempt number `attempt` (starting at 1), or `None` past the last. Never
+    // more than `max_delay`, however large the attempt or odd the multiplier.
+    pub(crate) fn delay(&self, attempt: u32) -> Option<Duration> {
+        if attempt == 0 || attempt > self.max_retries {
+            return None;
+        }
+
+        let max = self.max_delay.as_secs_f64();
+        let secs = self.initial_delay.as_secs_f64() * self.multiplier.powf(f64::from(attempt - 1));
+        // A NaN stays NaN through `clamp` and fails the conversion
+        let delay = Duration::try_from_secs_f64(secs.clamp(0.0, max)).unwrap_or(self.max_delay);
+        Some(delay.min(self.max_delay))
+    }
+}
+
+impl RetryPolicy for ExponentialBackoff {
+    fn next_delay(&self, attempt: u32, err: &ClientError) -> Option<Duration> {
+        if !matches!(err, ClientError::Io(_) | ClientError::Timeout) {
+            return None;
+        }
+        self.delay(attempt)
+    }
+}
@@ -0,0 +1,93 @@
+use crate::types::{LogEntry, LogLevel, LogSource};
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::SystemTime;
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+
+// Stderr lines kept per server before the oldest are dropped
+const STDERR_CAPACITY: usize = 1000;
+
+// Log notifications kept per server, counted separately so a chatty server can't push out the
+// stderr lines reported when it exits
+const NOTIFICATION_LOG_CAPACITY: usize = 1000;
+
+/// Bounded buffer of the most recent things a server has logged
+#[derive(Default)]
+pub(crate) struct LogBuffer {
+    entries: Mutex<Entries>,
+}
+
+// Entries by source, each numbered in arrival order so the two can be merged again
+#[derive(Default)]
+struct Entries {
+    next: u64,
+    stderr: VecDeque<(u64, LogEntry)>,
+    notifications: VecDeque<(u64, LogEntry)>,
+}
+
+impl LogBuffer {
+    pub(crate) fn push(&self, source: LogSource, level: LogLevel, message: String) {
+        let mut entries = self.entries.lock().unwrap();
+        let number = entries.next;
+        entries.next += 1;
+        let (kept, capacity) = match source {
+            LogSource::Stderr => (&mut entries.stderr, STDERR_CAPACITY),
+            LogSource::Notification => (&mut entries.notifications, NOTIFICATION_LOG_CAPACITY),
+        };
+        if kept.len() == capacity {
+            kept.pop_front();
+        }
+        kept.push_back((
+            number,
+            LogEntry {
+                source,
+                level,
+                message,
+                timestamp: SystemTime::now(),
+            },
+        ));
+    }
+
+    // The newest `limit` entries at or above `min_level`, oldest first
+    pub(crate) fn recent(&self, min_level: LogLevel, limit: usize) -> Vec<LogEntry> {
+        let entries = self.entries.lock().unwrap();
+        let mut recent: Vec<&(u64, LogEntry)> = entries
+            .stderr
+            .iter()
+            .chain(&entries.notifications)
+            .filter(|(_, entry)| entry.level >= min_level)
+            .collect();
+        recent.sort_by_key(|(number, _)| *number);
+        let skip = recent.len().saturating_sub(limit);
+        recent
+            .into_iter()
+            .skip(skip)
+            .map(|(_, entry)| entry.clone())
+            .collect()
+    }
+
+    // The newest `limit` stderr lines, oldest first
+    pub(crate) fn stderr_tail(&self, limit: usize) -> Vec<String> {
+        let entries = self.entries.lock().unwrap();
+        let skip = entries.stderr.len().saturating_sub(limit);
+        entries
+            .stderr
+            .iter()
+            .skip(skip)
+            .map(|(_, entry)| entry.message.clone())
+            .collect()
+    }
+}
+
+// Record every stderr line until the server closes it. Stderr carries no severity, so lines are
+// stored at `Info`.
+pub(crate) async fn capture_stderr(
+    stderr: impl AsyncRead + Unpin,
+    logs: std::sync::Arc<LogBuffer>,
+) {
+    let mut lines = BufReader::new(stderr).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        tracing::debug!(target: "mcp_client_rs::server", "{}", line);
+        logs.push(LogSource::Stderr, LogLevel::Info, line);
+    }
+}
@@ -0,0 +1,228 @@
+use crate::server_log::{capture_stderr, LogBuffer};
+use crate::ClientError;
+use futures::future::BoxFuture;
+use std::{collections::HashMap, process::Stdio, sync::Arc, time::Duration};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::process::{Child, ChildStdin, ChildStdout, Command};
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+
+/// How long [`Protocol::shutdown`](crate::Protocol::shutdown) waits for a stdio server to exit
+/// on its own before killing it
+pub const SHUTDOWN_GRACE: Duration = Duration::from_secs(2);
+
+// How long to wait for the rest of the server's stderr once its stdout closes
+pub(crate) const STDERR_DRAIN_GRACE: Duration = Duration::from_millis(200);
+
+/// Carries JSON-RPC messages between the client and a server.
+///
+/// A [`Protocol`](crate::Protocol) sends from any task but receives from a single reader task,
+/// so `recv` is never called concurrently with itself. Implementations report their own
+/// failures with [`ClientError::transport`].
+pub trait Transport: Send + Sync {
+    /// Send one JSON-RPC message. `message` is a complete JSON value without a trailing newline;
+    /// any framing is up to the transport.
+    fn send<'a>(&'a self, message: &'a str) -> BoxFuture<'a, Result<(), ClientError>>;
+
+    /// Wait for the next message from the server, or `Ok(None)` once the server has closed the
+    /// connection
+    fn recv(&self) -> BoxFuture<'_, Result<Option<String>, ClientError>>;
+
+    /// Close the connection, stopping the server if the transport owns it
+    fn close(&self) -> BoxFuture<'_, Result<(), ClientError>>;
+
+    /// Write `bytes` to the connection without any framing. Transports that can't do this
+    /// fail, which is the default.
+    fn send_raw<'a>(&'a self, bytes: &'a [u8]) -> BoxFuture<'a, Result<(), ClientError>> {
+        let _ = bytes;
+        Box::pin(async {
+            Err(ClientError::transport(
+                "raw writes are not supported by this transport",
+            ))
+        })
+    }
+}
+
+/// Talks to a server spawned as a child process over its stdin and stdout, one message per
+/// line. The server's stderr is recorded and shows up in
+/// [`Protocol::recent_logs`](crate::Protocol::recent_logs). The process is killed when the
+/// transport is dropped.
+pub struct StdioTransport {
+    stdin: Mutex<Stdin>,
+    // Frames waiting for whoever next holds `stdin`, and how many have been queued in total
+    queued: std::sync::Mutex<(Vec<u8>, u64)>,
+    stdout: Mutex<BufReader<ChildStdout>>,
+    child: Mutex<Child>,
+    // Task recording stderr, awaited briefly once stdout closes
+    stderr: Mutex<Option<JoinHandle<()>>>,
+    logs: Arc<LogBuffer>,
+}
+
+impl StdioTransport {
+    /// Spawn `program` with `args` and `envs`
+    pub fn spawn(
+        program: &str,
+        args: Vec<&str>,
+        envs: HashMap<String, String>,
+    ) -> Result<Self, ClientError> {
+        let mut command = Command::new(program);
+        command.args(args).envs(envs);
+        Self::from_command(command, Arc::new(LogBuffer::default()))
+    }
+
+    // Spawn `command` with its stdio piped to the transport, recording its stderr in `logs`
+    pub(crate) fn from_command(
+        mut command: Command,
+        logs: Arc<LogBuffer>,
+    ) -> Result<Self, ClientError> {
+        let mut child = command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+
+        let stdin = child.stdin.take().expect("Failed to get stdin");
+        let stdout = child.stdout.take().expect("Failed to get stdout");
+        let stderr = child.stderr.take().expect("Failed to get stderr");
+
+        let stderr = tokio::spawn(capture_stderr(stderr, logs.clone()));
+
+        Ok(Self {
+            stdin: Mutex::new(Stdin {
+                pipe: Some(stdin),
+                written: 0,
+                failed: None,
+            }),
+            queued: std::sync::Mutex::new((vec![], 0)),
+            stdout: Mutex::new(BufReader::new(stdout)),
+            child: Mutex::new(child),
+            stderr: Mutex::new(Some(stderr)),
+            logs,
+        })
+    }
+
+    // Buffer the server's stderr is recorded in
+    pub(crate) fn logs(&self) -> Arc<LogBuffer> {
+        self.logs.clone()
+    }
+
+    // Queue `bytes` and wait until they have been written. Whoever holds the stdin lock writes
+    // every frame queued so far in one go, so frames sent concurrently share a write syscall
+    // while a lone frame is still written straight away.
+    async fn write(&self, bytes: &[u8]) -> Result<(), ClientError> {
+        let frame = {
+            let mut queued = self.queued.lock().unwrap();
+            queued.0.extend_from_slice(bytes);
+            queued.1 += 1;
+            queued.1
+        };
+
+        let mut stdin = self.stdin.lock().await;
+        // Written along with an earlier frame
+        if frame <= stdin.written {
+            return Ok(());
+        }
+        if let Some((kind, message)) = &stdin.failed {
+            return Err(write_error(*kind, message.clone()));
+        }
+        let Some(pipe) = stdin.pipe.as_mut() else {
+            return Err(ClientError::ServerClosed { stderr: vec![] });
+        };
+
+        let (batch, last) = {
+            let mut queued = self.queued.lock().unwrap();
+            (std::mem::take(&mut queued.0), queued.1)
+        };
+        let written = match pipe.write_all(&batch).await {
+            Ok(()) => pipe.flush().await,
+            Err(err) => Err(err),
+        };
+        match written {
+            Ok(()) => {
+                stdin.written = last;
+                Ok(())
+            }
+            // Frames queued behind this batch fail the same way
+            Err(err) => {
+                stdin.failed = Some((err.kind(), err.to_string()));
+                Err(write_error(err.kind(), err.to_string()))
+            }
+        }
+    }
+}
+
+impl Transport for StdioTransport {
+    fn send<'a>(&'a self, message: &'a str) -> BoxFuture<'a, Result<(), ClientError>> {
+        Box::pin(async move {
+            // Write the message and its newline delimiter as a single frame
+            let mut frame = Vec::with_capacity(message.len() + 1);
+            frame.extend_from_slice(message.as_bytes());
+            frame.push(b'\n');
+            self.write(&frame).await
+        })
+    }
+
+    fn recv(&self) -> BoxFuture<'_, Result<Option<String>, ClientError>> {
+        Box::pin(async move {
+            let mut line = Vec::new();
+            let read = self
+                .stdout
+                .lock()
+                .await
+                .read_until(b'\n', &mut line)
+                .await?;
+            if read == 0 {
+                // Give the stderr task a moment to record why the server went away, so the
+                // errors handed to pending requests include it
+                if let Some(stderr) = self.stderr.lock().await.take() {
+                    let _ = tokio::time::timeout(STDERR_DRAIN_GRACE, stderr).await;
+                }
+                return Ok(None);
+            }
+            Ok(Some(String::from_utf8_lossy(&line).into_owned()))
+        })
+    }
+
+    fn close(&self) -> BoxFuture<'_, Result<(), ClientError>> {
+        Box::pin(async move {
+            self.stdin.lock().await.pipe.take();
+            let mut child = self.child.lock().await;
+            match tokio::time::timeout(SHUTDOWN_GRACE, child.wait()).await {
+                Ok(exited) => {
+                    exited?;
+                }
+                Err(_) => child.kill().await?,
+            }
+            Ok(())
+        })
+    }
+
+    fn send_raw<'a>(&'a self, bytes: &'a [u8]) -> BoxFuture<'a, Result<(), ClientError>> {
+        Box::pin(self.write(bytes))
+    }
+}
+
+impl Drop for StdioTransport {
+    // Don't leave the server running once nothing can talk to it
+    fn drop(&mut self) {
+        let _ = self.child.get_mut().start_kill();
+    }
+}
+
+// The server's stdin and how far writing to it has got
+struct Stdin {
+    // Taken by `close` to signal end of input to the server
+    pipe: Option<ChildStdin>,
+    // Number of queued frames written so far
+    written: u64,
+    // Set once a write fails, as every later frame would fail too
+    failed: Option<(std::io::ErrorKind, String)>,
+}
+
+fn write_error(kind: std::io::ErrorKind, message: String) -> ClientError {
+    match kind {
+        // The server stopped reading its input
+        std::io::ErrorKind::BrokenPipe => ClientError::ServerClosed { stderr: vec![] },
+        kind => std::io::Error::new(kind, message).into(),
+    }
+}
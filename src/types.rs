@@ -12,12 +12,25 @@ pub struct InitializeResponse {
     pub meta: Option<HashMap<String, serde_json::Value>>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Clone)]
 pub struct ServerInfo {
     pub name: String,
     pub version: String,
 }
 
+/// Everything known about a connected server, see `Protocol::describe`
+#[derive(Debug, Clone)]
+pub struct ServerProfile {
+    pub name: String,
+    pub version: String,
+    pub protocol_version: String,
+    pub capabilities: ServerCapabilities,
+    /// `None` when the server does not advertise tools
+    pub tool_count: Option<usize>,
+    /// `None` when the server does not advertise resources
+    pub resource_count: Option<usize>,
+}
+
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ResourcesReadResponse {
@@ -26,12 +39,33 @@ pub struct ResourcesReadResponse {
     pub meta: Option<HashMap<String, serde_json::Value>>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+impl ResourcesReadResponse {
+    /// All content entries returned for `uri`; a single uri (e.g. a directory) can map to
+    /// several entries
+    pub fn by_uri(&self, uri: &str) -> Vec<&ResourceContents> {
+        let parsed = Url::parse(uri).ok();
+        self.contents
+            .iter()
+            .filter(|c| match &parsed {
+                Some(parsed) => &c.uri == parsed,
+                None => c.uri.as_str() == uri,
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct ResourceContents {
     pub uri: Url,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub mime_type: Option<String>,
+    /// Text content, for text resources
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    /// Base64-encoded content, for binary resources
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub blob: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -51,48 +85,558 @@ pub struct Resource {
     pub resource_type: String,
 }
 
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceTemplatesResponse {
+    pub resource_templates: Vec<ResourceTemplate>,
+    #[serde(default)]
+    pub next_cursor: Option<String>,
+}
+
+/// A parameterized resource, e.g. `file:///{path}`
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceTemplate {
+    /// RFC 6570 uri template
+    pub uri_template: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub mime_type: Option<String>,
+}
+
 #[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct ListToolsResponse {
     pub tools: Vec<Tool>,
+    /// Cursor for the next page, if there is one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
-#[serde(rename_all = "snake_case")]
+#[serde(rename_all = "camelCase")]
 pub struct Tool {
     pub name: String,
     pub description: String,
-    #[serde(rename(deserialize = "inputSchema"))]
     pub input_schema: serde_json::Value,
 }
 
+impl Tool {
+    /// Names in the input schema's `required` array, empty if it has none
+    pub fn required_params(&self) -> Vec<String> {
+        self.input_schema["required"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|name| name.as_str().map(str::to_string))
+            .collect()
+    }
+}
+
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct CallToolResponse {
     pub content: Vec<ToolResponseContent>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub is_error: Option<bool>,
+    #[serde(default)]
+    pub is_error: bool,
     #[serde(rename = "_meta", skip_serializing_if = "Option::is_none")]
     pub meta: Option<HashMap<String, serde_json::Value>>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+impl CallToolResponse {
+    /// Collapse the response into its text output: `Ok` with the text content joined by
+    /// newlines, or `Err` with that text when the tool reported an error. Non-text content is
+    /// skipped.
+    pub fn into_result(self) -> Result<String, String> {
+        let text = self
+            .content
+            .into_iter()
+            .filter_map(|content| match content {
+                ToolResponseContent::Text { text, .. } => Some(text),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        if self.is_error {
+            Err(text)
+        } else {
+            Ok(text)
+        }
+    }
+
+    /// Resources the tool referenced without embedding, to be fetched separately with
+    /// [`Protocol::read_resource`](crate::Protocol::read_resource)
+    pub fn resource_links(&self) -> Vec<&ResourceLink> {
+        self.content
+            .iter()
+            .filter_map(|content| match content {
+                ToolResponseContent::ResourceLink(link) => Some(link),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(tag = "type")]
 pub enum ToolResponseContent {
     #[serde(rename = "text")]
-    Text { text: String },
+    Text {
+        text: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        annotations: Option<Annotations>,
+    },
     #[serde(rename = "image")]
-    Image { data: String, mime_type: String },
+    Image {
+        data: String,
+        #[serde(rename = "mimeType")]
+        mime_type: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        annotations: Option<Annotations>,
+    },
     #[serde(rename = "resource")]
-    Resource { resource: ResourceContents },
+    Resource {
+        resource: ResourceContents,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        annotations: Option<Annotations>,
+    },
+    #[serde(rename = "resource_link")]
+    ResourceLink(ResourceLink),
 }
 
-#[derive(Debug, Deserialize)]
-pub struct Prompt {
-    pub id: String,
-    pub description: String,
+/// A reference to a resource by uri, returned in place of embedding large outputs
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceLink {
+    pub uri: Url,
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mime_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub annotations: Option<Annotations>,
+}
+
+impl ToolResponseContent {
+    pub fn text(text: impl Into<String>) -> Self {
+        ToolResponseContent::Text {
+            text: text.into(),
+            annotations: None,
+        }
+    }
+
+    /// Image content from base64-encoded `data`
+    pub fn image(data: impl Into<String>, mime_type: impl Into<String>) -> Self {
+        ToolResponseContent::Image {
+            data: data.into(),
+            mime_type: mime_type.into(),
+            annotations: None,
+        }
+    }
+
+    pub fn resource(resource: ResourceContents) -> Self {
+        ToolResponseContent::Resource {
+            resource,
+            annotations: None,
+        }
+    }
+
+    /// Replace the content's annotations
+    pub fn with_annotations(mut self, new: Annotations) -> Self {
+        match &mut self {
+            ToolResponseContent::Text { annotations, .. }
+            | ToolResponseContent::Image { annotations, .. }
+            | ToolResponseContent::Resource { annotations, .. } => *annotations = Some(new),
+            ToolResponseContent::ResourceLink(link) => link.annotations = Some(new),
+        }
+        self
+    }
+
+    pub fn annotations(&self) -> Option<&Annotations> {
+        match self {
+            ToolResponseContent::Text { annotations, .. }
+            | ToolResponseContent::Image { annotations, .. }
+            | ToolResponseContent::Resource { annotations, .. } => annotations.as_ref(),
+            ToolResponseContent::ResourceLink(link) => link.annotations.as_ref(),
+        }
+    }
+
+    /// Whether this content is intended for `role`. Content without an audience annotation is
+    /// intended for everyone.
+    pub fn is_for_audience(&self, role: Role) -> bool {
+        self.annotations()
+            .and_then(|a| a.audience.as_ref())
+            .is_none_or(|audience| audience.contains(&role))
+    }
+}
+
+/// Renders text content as-is and a short placeholder for everything else
+impl fmt::Display for ToolResponseContent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ToolResponseContent::Text { text, .. } => f.write_str(text),
+            ToolResponseContent::Image { mime_type, .. } => write!(f, "[image: {}]", mime_type),
+            ToolResponseContent::Resource { resource, .. } => match &resource.text {
+                Some(text) => f.write_str(text),
+                None => write!(f, "[resource: {}]", resource.uri),
+            },
+            ToolResponseContent::ResourceLink(link) => write!(f, "[resource link: {}]", link.uri),
+        }
+    }
+}
+
+/// Hints about how a client should use a piece of content
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct Annotations {
+    /// Who the content is intended for
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub audience: Option<Vec<Role>>,
+    /// Importance from 0.0 (optional) to 1.0 (required)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority: Option<f64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    User,
+    Assistant,
+}
+
+/// A directory or file the client exposes to the server, answered on `roots/list`
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Root {
+    pub uri: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+}
+
+/// Syslog severity used by MCP logging, ordered from least to most severe
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Debug,
+    Info,
+    Notice,
+    Warning,
+    Error,
+    Critical,
+    Alert,
+    Emergency,
+}
+
+/// Where a server log line came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogSource {
+    /// A line the server process wrote to stderr
+    Stderr,
+    /// A `notifications/message` sent by the server
+    Notification,
+}
+
+/// Which way a message passed to [`Protocol::on_raw_message`](crate::Protocol::on_raw_message)
+/// was going
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// Sent by the client
+    Outbound,
+    /// Received from the server
+    Inbound,
+}
+
+/// Whether requests can currently reach the server, see
+/// [`Protocol::connection_state`](crate::Protocol::connection_state)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionState {
+    /// Requests are sent as usual
+    Connected,
+    /// The server exited and is being restarted; requests wait until it is back
+    Reconnecting,
+    /// The connection was shut down, or the server exited and was not restarted
+    Closed,
+}
+
+/// A notification pushed by the server, as delivered by
+/// [`Protocol::notifications`](crate::Protocol::notifications)
+#[derive(Debug, Clone)]
+pub enum ServerNotification {
+    /// A subscribed resource changed
+    ResourceUpdated {
+        uri: String,
+    },
+    ResourceListChanged,
+    ToolListChanged,
+    PromptListChanged,
+    /// A log message, also recorded in the server's recent logs
+    Message {
+        level: LogLevel,
+        logger: Option<String>,
+        data: serde_json::Value,
+    },
+    Progress {
+        progress_token: serde_json::Value,
+        progress: f64,
+        total: Option<f64>,
+        message: Option<String>,
+    },
+    /// The server gave up on a request it sent us
+    Cancelled {
+        request_id: serde_json::Value,
+        reason: Option<String>,
+    },
+    /// One message of a streamed `prompts/get`, see
+    /// [`Protocol::get_prompt_stream`](crate::Protocol::get_prompt_stream)
+    PromptMessage {
+        progress_token: serde_json::Value,
+        message: PromptMessage,
+    },
+    /// Any other method, or a known one whose params didn't have the expected shape
+    Other {
+        method: String,
+        params: serde_json::Value,
+    },
+}
+
+#[derive(Deserialize)]
+struct LoggingMessageParams {
+    level: LogLevel,
+    logger: Option<String>,
+    data: serde_json::Value,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ProgressParams {
+    progress_token: serde_json::Value,
+    progress: f64,
+    total: Option<f64>,
+    message: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CancelledParams {
+    request_id: serde_json::Value,
+    reason: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PromptMessageParams {
+    progress_token: serde_json::Value,
+    message: PromptMessage,
+}
+
+impl ServerNotification {
+    pub(crate) fn parse(method: &str, params: serde_json::Value) -> Self {
+        let parsed =
+            match method {
+                "notifications/resources/updated" => {
+                    params["uri"]
+                        .as_str()
+                        .map(|uri| ServerNotification::ResourceUpdated {
+                            uri: uri.to_string(),
+                        })
+                }
+                "notifications/resources/list_changed" => {
+                    Some(ServerNotification::ResourceListChanged)
+                }
+                "notifications/tools/list_changed" => Some(ServerNotification::ToolListChanged),
+                "notifications/prompts/list_changed" => Some(ServerNotification::PromptListChanged),
+                "notifications/message" => {
+                    LoggingMessageParams::deserialize(&params).ok().map(|p| {
+                        ServerNotification::Message {
+                            level: p.level,
+                            logger: p.logger,
+                            data: p.data,
+                        }
+                    })
+                }
+                "notifications/progress" => ProgressParams::deserialize(&params).ok().map(|p| {
+                    ServerNotification::Progress {
+                        progress_token: p.progress_token,
+                        progress: p.progress,
+                        total: p.total,
+                        message: p.message,
+                    }
+                }),
+                "notifications/cancelled" => CancelledParams::deserialize(&params).ok().map(|p| {
+                    ServerNotification::Cancelled {
+                        request_id: p.request_id,
+                        reason: p.reason,
+                    }
+                }),
+                "notifications/prompts/message" => PromptMessageParams::deserialize(&params)
+                    .ok()
+                    .map(|p| ServerNotification::PromptMessage {
+                        progress_token: p.progress_token,
+                        message: p.message,
+                    }),
+                _ => None,
+            };
+        parsed.unwrap_or_else(|| ServerNotification::Other {
+            method: method.to_string(),
+            params,
+        })
+    }
+}
+
+/// Progress reported by the server for a long-running request
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProgressUpdate {
+    pub progress: f64,
+    pub total: Option<f64>,
+    pub message: Option<String>,
 }
 
 #[derive(Debug, Clone)]
+pub struct LogEntry {
+    pub source: LogSource,
+    pub level: LogLevel,
+    pub message: String,
+    pub timestamp: std::time::SystemTime,
+}
+
+/// A server entry in an `mcpServers` config file, as used by Claude Desktop
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct McpServerConfig {
+    pub command: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    /// Most requests sent to this server at once; unlimited when absent
+    #[serde(
+        default,
+        rename = "maxInFlight",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub max_in_flight: Option<usize>,
+}
+
+/// Top-level `{ "mcpServers": { name: McpServerConfig } }` config file
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct McpConfig {
+    pub mcp_servers: HashMap<String, McpServerConfig>,
+}
+
+/// A server's `sampling/createMessage` request, asking the client to generate a message with
+/// its language model
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateMessageParams {
+    pub messages: Vec<SamplingMessage>,
+    /// Hints for choosing a model, passed through as sent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model_preferences: Option<serde_json::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub system_prompt: Option<String>,
+    /// Which servers' context to include: `none`, `thisServer` or `allServers`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub include_context: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f64>,
+    pub max_tokens: u32,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub stop_sequences: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<serde_json::Value>,
+}
+
+/// One message of the conversation in a sampling request
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SamplingMessage {
+    pub role: Role,
+    pub content: ToolResponseContent,
+}
+
+/// The client's answer to a server's `sampling/createMessage` request
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateMessageResult {
+    pub role: Role,
+    pub content: ToolResponseContent,
+    /// Name of the model that generated the message
+    pub model: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stop_reason: Option<StopReason>,
+}
+
+/// Why sampling stopped
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum StopReason {
+    EndTurn,
+    StopSequence,
+    MaxTokens,
+    /// Any other provider-specific reason
+    #[serde(untagged)]
+    Other(String),
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct Prompt {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub arguments: Vec<PromptArgument>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct PromptArgument {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub required: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ListPromptsResponse {
+    pub prompts: Vec<Prompt>,
+    #[serde(default)]
+    pub next_cursor: Option<String>,
+}
+
+/// Result of `prompts/get`: the prompt rendered into messages
+#[derive(Debug, Deserialize)]
+pub struct PromptGetResponse {
+    #[serde(default)]
+    pub description: Option<String>,
+    pub messages: Vec<PromptMessage>,
+}
+
+/// Suggestions returned by `completion/complete`
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CompletionResponse {
+    pub values: Vec<String>,
+    /// Total number of matches, which may exceed the values returned
+    #[serde(default)]
+    pub total: Option<u64>,
+    #[serde(default)]
+    pub has_more: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct CompleteResult {
+    pub completion: CompletionResponse,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct PromptMessage {
+    pub role: Role,
+    pub content: ToolResponseContent,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum RequestType {
     Initialize,
     CallTool,
@@ -108,6 +652,10 @@ pub enum RequestType {
     ListTools,
     ListResourceTemplates,
     ListRoots,
+    Initialized,
+    Cancelled,
+    Progress,
+    RootsListChanged,
 }
 
 #[derive(Debug, PartialEq, Clone, Copy)]
@@ -120,6 +668,17 @@ pub enum ServerCapability {
     Sampling,
 }
 
+impl ServerCapability {
+    pub const ALL: [ServerCapability; 6] = [
+        ServerCapability::Experimental,
+        ServerCapability::Logging,
+        ServerCapability::Prompts,
+        ServerCapability::Resources,
+        ServerCapability::Tools,
+        ServerCapability::Sampling,
+    ];
+}
+
 impl RequestType {
     pub fn as_str(&self) -> &'static str {
         match self {
@@ -137,50 +696,68 @@ impl RequestType {
             RequestType::ListTools => "tools/list",
             RequestType::ListResourceTemplates => "resources/templates/list",
             RequestType::ListRoots => "roots/list",
+            RequestType::Initialized => "notifications/initialized",
+            RequestType::Cancelled => "notifications/cancelled",
+            RequestType::Progress => "notifications/progress",
+            RequestType::RootsListChanged => "notifications/roots/list_changed",
         }
     }
+
+    /// Whether sending the request twice has the same effect as sending it once. Tool calls
+    /// may have side effects, so they aren't.
+    pub fn is_idempotent(&self) -> bool {
+        !matches!(self, RequestType::CallTool)
+    }
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ServerCapabilities {
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub experimental: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub logging: Option<LoggingCapability>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub prompts: Option<PromptsCapability>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub resources: Option<ResourcesCapability>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub tools: Option<ToolsCapability>,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub sampling: Option<SamplingCapability>,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct LoggingCapability {
     pub levels: Vec<String>,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct PromptsCapability {
     pub supports_custom: bool,
+    #[serde(default)]
+    pub supports_streaming: bool,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ResourcesCapability {
     pub supports_subscribe: bool,
     pub supports_delta: bool,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ToolsCapability {
     #[serde(default)]
     pub supports_streaming: bool,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct SamplingCapability {
     pub max_tokens: Option<u32>,
     pub supported_methods: Vec<String>,
 }
 
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum ClientError {
     Io(std::io::Error),
     InitializationFailed(String),
@@ -190,6 +767,41 @@ pub enum ClientError {
     CapabilityError(String),
     SerializationError(String),
     ProtocolError(String),
+    /// A successful result did not have the expected shape. `raw` holds the result as sent.
+    ResponseDeserialization {
+        message: String,
+        raw: serde_json::Value,
+    },
+    /// A request needing negotiated capabilities was made before `initialize` completed
+    NotInitialized,
+    /// Failure inside a transport, wrapping the transport's own error type
+    Transport(Box<dyn std::error::Error + Send + Sync>),
+    /// The request did not complete in the time allowed
+    Timeout,
+    /// The request was cancelled with [`Protocol::cancel`](crate::Protocol::cancel)
+    Cancelled,
+    /// The server answered the request with a JSON-RPC error, e.g. code -32601 for a method
+    /// it doesn't implement or -32602 for invalid params
+    Rpc {
+        code: i32,
+        message: String,
+        data: Option<serde_json::Value>,
+    },
+    /// The server closed its end of the connection; no further requests can be sent.
+    /// `stderr` holds the last lines the server wrote to stderr, oldest first.
+    ServerClosed {
+        stderr: Vec<String>,
+    },
+    /// The server exited while the request was in flight. It was restarted, but the request
+    /// was not sent again since repeating it may not be safe.
+    Disconnected,
+}
+
+impl ClientError {
+    /// Wrap an error raised by a transport implementation
+    pub fn transport(err: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> Self {
+        ClientError::Transport(err.into())
+    }
 }
 
 impl From<std::io::Error> for ClientError {
@@ -198,6 +810,23 @@ impl From<std::io::Error> for ClientError {
     }
 }
 
+#[cfg(feature = "http")]
+impl From<reqwest::Error> for ClientError {
+    fn from(err: reqwest::Error) -> Self {
+        ClientError::Transport(Box::new(err))
+    }
+}
+
+impl From<JsonRpcError> for ClientError {
+    fn from(error: JsonRpcError) -> Self {
+        ClientError::Rpc {
+            code: error.code,
+            message: error.message,
+            data: error.data,
+        }
+    }
+}
+
 impl From<serde_json::Error> for ClientError {
     fn from(err: serde_json::Error) -> Self {
         ClientError::SerializationError(err.to_string())
@@ -217,6 +846,32 @@ impl fmt::Display for ClientError {
             ClientError::CapabilityError(msg) => write!(f, "Capability error: {}", msg),
             ClientError::SerializationError(msg) => write!(f, "Serialization error: {}", msg),
             ClientError::ProtocolError(msg) => write!(f, "Protocol error: {}", msg),
+            ClientError::ResponseDeserialization { message, .. } => {
+                write!(f, "Response deserialization error: {}", message)
+            }
+            ClientError::NotInitialized => {
+                write!(f, "Client is not initialized; call initialize first")
+            }
+            ClientError::Transport(err) => write!(f, "Transport error: {}", err),
+            ClientError::Timeout => write!(f, "Request timed out"),
+            ClientError::Cancelled => write!(f, "Request was cancelled"),
+            ClientError::Rpc { code, message, .. } => {
+                write!(f, "Server returned error {}: {}", code, message)
+            }
+            ClientError::Disconnected => {
+                write!(
+                    f,
+                    "Server exited before answering; the request was not resent"
+                )
+            }
+            ClientError::ServerClosed { stderr } if stderr.is_empty() => {
+                write!(f, "Server closed the connection")
+            }
+            ClientError::ServerClosed { stderr } => write!(
+                f,
+                "Server closed the connection; last stderr output:\n{}",
+                stderr.join("\n")
+            ),
         }
     }
 }
@@ -225,6 +880,7 @@ impl std::error::Error for ClientError {
     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
         match self {
             ClientError::Io(err) => Some(err),
+            ClientError::Transport(err) => Some(err.as_ref()),
             _ => None,
         }
     }
@@ -239,6 +895,25 @@ pub struct JsonRpcRequest<T> {
     params: T,
 }
 
+/// A JSON-RPC message with no id, which the server never answers
+#[derive(Serialize)]
+pub struct JsonRpcNotification<T> {
+    jsonrpc: String,
+    #[serde(serialize_with = "serialize_request_type")]
+    method: RequestType,
+    params: T,
+}
+
+impl<T> JsonRpcNotification<T> {
+    pub fn new(method: RequestType, params: T) -> Self {
+        Self {
+            jsonrpc: "2.0".to_string(),
+            method,
+            params,
+        }
+    }
+}
+
 fn serialize_request_type<S>(request_type: &RequestType, serializer: S) -> Result<S::Ok, S::Error>
 where
     S: serde::Serializer,
@@ -251,13 +926,38 @@ pub struct InitializeParams {
     // Changed from protocol_version to protocolVersion to match server requirements
     #[serde(rename = "protocolVersion")]
     pub protocol_version: String,
-    pub capabilities: serde_json::Value,
+    pub capabilities: ClientCapabilities,
     // Changed from client_info to clientInfo to match server requirements
     #[serde(rename = "clientInfo")]
     pub client_info: ClientInfo,
 }
 
-#[derive(Serialize)]
+/// Features this client offers the server, advertised during initialization. Nothing is
+/// advertised by default.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ClientCapabilities {
+    /// The client answers `roots/list`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub roots: Option<RootsCapability>,
+    /// The client handles `sampling/createMessage`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sampling: Option<ClientSamplingCapability>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub experimental: Option<HashMap<String, serde_json::Value>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct RootsCapability {
+    /// The client notifies the server when its roots change
+    #[serde(default)]
+    pub list_changed: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ClientSamplingCapability {}
+
+#[derive(Serialize, Clone)]
 pub struct ClientInfo {
     pub name: String,
     pub version: String,
@@ -267,13 +967,16 @@ pub struct ClientInfo {
 pub struct ToolCallParams {
     pub name: String,
     pub arguments: serde_json::Value,
+    #[serde(rename = "_meta", skip_serializing_if = "Option::is_none")]
+    pub meta: Option<serde_json::Value>,
 }
 
 // Response handling structures
 #[derive(Deserialize, Debug, Clone)]
 pub struct JsonRpcResponse<T> {
     pub jsonrpc: String,
-    pub id: u64,
+    /// `None` when the server could not determine the request id, e.g. for parse errors
+    pub id: Option<u64>,
     #[serde(flatten)]
     pub response: ResponseContent<T>,
 }
@@ -286,11 +989,21 @@ pub enum ResponseContent<T> {
 }
 
 #[derive(Deserialize, Debug, Clone)]
-struct JsonRpcError {
-    code: i32,
-    message: String,
+pub struct JsonRpcError {
+    pub code: i32,
+    pub message: String,
     #[serde(skip_serializing_if = "Option::is_none")]
-    data: Option<serde_json::Value>,
+    pub data: Option<serde_json::Value>,
+}
+
+impl<T> JsonRpcResponse<T> {
+    /// The result of a successful response, or the server's error as [`ClientError::Rpc`]
+    pub fn into_result(self) -> Result<T, ClientError> {
+        match self.response {
+            ResponseContent::Success { result } => Ok(result),
+            ResponseContent::Error { error } => Err(error.into()),
+        }
+    }
 }
 
 // Request builder implementation
@@ -303,4 +1016,12 @@ impl<T> JsonRpcRequest<T> {
             params,
         }
     }
+
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    pub fn method(&self) -> &RequestType {
+        &self.method
+    }
 }
@@ -0,0 +1,154 @@
+mod common;
+
+use common::FakeServer;
+use mcp_client_rs::{ClientError, LogLevel, Protocol, ProtocolBuilder};
+use serde_json::{json, Value};
+use std::time::Duration;
+
+// Build a client whose server reports something on stderr, returning the reported line
+async fn reported(builder: ProtocolBuilder) -> String {
+    let client = builder.build().await.unwrap();
+    for _ in 0..50 {
+        if let Some(entry) = client.recent_logs(LogLevel::Debug, 1).pop() {
+            return entry.message;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    panic!("the server reported nothing");
+}
+
+fn script() -> String {
+    format!(
+        "echo \"value=$MCP_TEST_VALUE parent=${{MCP_TEST_PARENT:-unset}}\" >&2; {}",
+        FakeServer::new().script()
+    )
+}
+
+#[tokio::test]
+async fn sets_args_and_env_one_at_a_time() {
+    std::env::set_var("MCP_TEST_PARENT", "inherited");
+    let script = script();
+
+    let inherited = reported(
+        Protocol::builder()
+            .program("sh")
+            .arg("-c")
+            .arg(&script)
+            .env("MCP_TEST_VALUE", "set"),
+    )
+    .await;
+    assert_eq!(inherited, "value=set parent=inherited");
+
+    let isolated = reported(
+        Protocol::builder()
+            .program("sh")
+            .arg("-c")
+            .arg(&script)
+            .env("MCP_TEST_VALUE", "set")
+            .env("PATH", &std::env::var("PATH").unwrap())
+            .inherit_env(false),
+    )
+    .await;
+    assert_eq!(isolated, "value=set parent=unset");
+}
+
+#[tokio::test]
+async fn requires_a_program_or_transport() {
+    assert!(matches!(
+        Protocol::builder().arg("-y").build().await,
+        Err(ClientError::InitializationFailed(_))
+    ));
+}
+
+#[tokio::test]
+async fn inherits_only_listed_env_vars() {
+    std::env::set_var("MCP_TEST_LISTED", "listed");
+    let script = format!(
+        "echo \"$MCP_TEST_LISTED ${{MCP_TEST_UNLISTED:-unset}}\" >&2; {}",
+        FakeServer::new().script()
+    );
+    std::env::set_var("MCP_TEST_UNLISTED", "unlisted");
+
+    let reported = reported(
+        Protocol::builder()
+            .program("sh")
+            .args(vec!["-c", &script])
+            .inherit_env_vars(vec!["PATH", "MCP_TEST_LISTED"]),
+    )
+    .await;
+    assert_eq!(reported, "listed unset");
+}
+
+#[tokio::test]
+async fn runs_the_server_in_current_dir() {
+    let dir = std::env::temp_dir().canonicalize().unwrap();
+    let script = format!("pwd >&2; {}", FakeServer::new().script());
+
+    let reported = reported(
+        Protocol::builder()
+            .program("sh")
+            .args(vec!["-c", &script])
+            .with_cwd(&dir),
+    )
+    .await;
+    assert_eq!(reported, dir.display().to_string());
+}
+
+#[tokio::test]
+async fn missing_current_dir_is_an_io_error() {
+    let result = Protocol::builder()
+        .program("sh")
+        .with_cwd("/nonexistent/mcp-client-rs")
+        .build()
+        .await;
+    match result {
+        Err(ClientError::Io(err)) => {
+            assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+            assert!(err.to_string().contains("/nonexistent/mcp-client-rs"));
+        }
+        Err(other) => panic!("expected an I/O error, got {:?}", other),
+        Ok(_) => panic!("expected an I/O error"),
+    }
+}
+
+#[tokio::test]
+async fn per_call_meta_takes_precedence_over_default_meta() {
+    let path = std::env::temp_dir().join(format!("mcp-call-meta-{}.jsonl", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let script = FakeServer::new()
+        .record_to(&path)
+        .result(
+            "tools/call",
+            json!({ "content": [{ "type": "text", "text": "done" }] }),
+        )
+        .script();
+
+    let client = Protocol::builder()
+        .program("sh")
+        .args(vec!["-c", &script])
+        .default_meta(json!({ "traceId": "default", "tenant": "acme" }))
+        .build()
+        .await
+        .unwrap();
+    client.call_tool("echo", json!({})).await.unwrap();
+    client
+        .call_tool_with_meta("echo", json!({}), json!({ "traceId": "call-1" }))
+        .await
+        .unwrap();
+
+    let recorded = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    let metas: Vec<Value> = recorded
+        .lines()
+        .map(|line| serde_json::from_str::<Value>(line).unwrap())
+        .filter(|request| request["method"] == "tools/call")
+        .map(|request| request["params"]["_meta"].clone())
+        .collect();
+    assert_eq!(
+        metas,
+        [
+            json!({ "traceId": "default", "tenant": "acme" }),
+            json!({ "traceId": "call-1", "tenant": "acme" })
+        ]
+    );
+}
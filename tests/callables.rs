@@ -0,0 +1,53 @@
+mod common;
+
+use common::FakeServer;
+use mcp_client_rs::{CallableOutput, ProtocolManager};
+use serde_json::json;
+
+#[tokio::test]
+async fn tools_and_prompts_are_invoked_uniformly() {
+    let server = FakeServer::new()
+        .result(
+            "tools/list",
+            json!({ "tools": [{ "name": "echo", "description": "Echo input", "inputSchema": {} }] }),
+        )
+        .result(
+            "tools/call",
+            json!({ "content": [{ "type": "text", "text": "echoed" }] }),
+        )
+        .result(
+            "prompts/list",
+            json!({ "prompts": [{ "name": "review", "arguments": [{ "name": "code", "required": true }] }] }),
+        )
+        .result(
+            "prompts/get",
+            json!({ "messages": [{ "role": "user", "content": { "type": "text", "text": "Review this" } }] }),
+        );
+    let config = std::env::temp_dir().join(format!("mcp-callables-{}.json", std::process::id()));
+    let servers =
+        json!({ "mcpServers": { "fake": { "command": "sh", "args": ["-c", server.script()] } } });
+    std::fs::write(&config, servers.to_string()).unwrap();
+
+    let manager = ProtocolManager::from_config(&config).await.unwrap();
+    std::fs::remove_file(&config).unwrap();
+
+    let callables = manager.list_callables().await.unwrap();
+    let names: Vec<_> = callables
+        .iter()
+        .map(|c| (c.client_id(), c.name()))
+        .collect();
+    assert_eq!(names, [("fake", "echo"), ("fake", "review")]);
+
+    match callables[0].invoke(json!({})).await.unwrap() {
+        CallableOutput::Tool(response) => assert_eq!(response.into_result(), Ok("echoed".into())),
+        other => panic!("expected tool output, got {:?}", other),
+    }
+    match callables[1]
+        .invoke(json!({ "code": "fn main() {}" }))
+        .await
+        .unwrap()
+    {
+        CallableOutput::Prompt(prompt) => assert_eq!(prompt.messages.len(), 1),
+        other => panic!("expected prompt output, got {:?}", other),
+    }
+}
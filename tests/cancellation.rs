@@ -0,0 +1,45 @@
+mod common;
+
+use common::FakeServer;
+use mcp_client_rs::ClientError;
+use serde_json::{json, Value};
+use std::time::Duration;
+
+#[tokio::test]
+async fn cancelled_call_fails_and_notifies_the_server() {
+    let path = std::env::temp_dir().join(format!("mcp-cancel-{}.jsonl", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    let client = FakeServer::new()
+        .record_to(&path)
+        .slow(
+            "tools/call",
+            json!({ "content": [{ "type": "text", "text": "slow result" }] }),
+            0.5,
+        )
+        .result("ping", json!({}))
+        .connect()
+        .await
+        .unwrap();
+
+    let call = client.call_tool_cancellable("slow", json!({}));
+    let id = call.id();
+    let (result, cancelled) = futures::future::join(call, async {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        client.cancel(id).await
+    })
+    .await;
+    cancelled.unwrap();
+    assert!(matches!(result, Err(ClientError::Cancelled)));
+
+    // The server reads the notification before the ping, and the late tool result is dropped
+    client.ping().await.unwrap();
+    let recorded = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    let notification = recorded
+        .lines()
+        .map(|line| serde_json::from_str::<Value>(line).unwrap())
+        .find(|message| message["method"] == "notifications/cancelled")
+        .expect("no notifications/cancelled sent");
+    assert_eq!(notification["params"]["requestId"], id);
+}
@@ -0,0 +1,147 @@
+use super::initialize_result;
+use futures::future::BoxFuture;
+use mcp_client_rs::{ClientError, Transport, DEFAULT_PROTOCOL_VERSION};
+use serde_json::{json, Value};
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+};
+use tokio::sync::mpsc;
+
+/// A transport playing a scripted server. Each message the client sends must equal the next
+/// expected one, ignoring the request id; requests are answered with the canned reply under
+/// their id. Anything unexpected panics, failing the test.
+///
+/// Clones share the script, so a test can keep a handle to inspect what was sent after giving
+/// one to the client.
+#[derive(Clone)]
+pub struct MockTransport {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    expected: Mutex<VecDeque<(Value, Option<Value>)>>,
+    sent: Mutex<Vec<Value>>,
+    replies: mpsc::UnboundedSender<String>,
+    incoming: tokio::sync::Mutex<mpsc::UnboundedReceiver<String>>,
+}
+
+impl MockTransport {
+    pub fn new() -> Self {
+        let (replies, incoming) = mpsc::unbounded_channel();
+        Self {
+            inner: Arc::new(Inner {
+                expected: Mutex::new(VecDeque::new()),
+                sent: Mutex::new(vec![]),
+                replies,
+                incoming: tokio::sync::Mutex::new(incoming),
+            }),
+        }
+    }
+
+    /// Expect the default initialize request, answered with [`initialize_result`], followed by
+    /// the initialized notification
+    pub fn handshake(self) -> Self {
+        self.handshake_with(initialize_result())
+    }
+
+    /// Like [`Self::handshake`], answering initialize with `result`
+    pub fn handshake_with(self, result: Value) -> Self {
+        self.expect(
+            "initialize",
+            json!({
+                "protocolVersion": DEFAULT_PROTOCOL_VERSION,
+                "capabilities": {},
+                "clientInfo": { "name": "mcp-client-rs", "version": env!("CARGO_PKG_VERSION") },
+            }),
+            result,
+        )
+        .expect_notification("notifications/initialized", json!({}))
+    }
+
+    /// Expect a `method` request with exactly `params`, answered with `result`
+    pub fn expect(self, method: &str, params: Value, result: Value) -> Self {
+        self.push(method, params, json!({ "result": result }))
+    }
+
+    /// Expect a `method` request with exactly `params`, answered with a JSON-RPC error
+    pub fn expect_error(self, method: &str, params: Value, code: i32, message: &str) -> Self {
+        self.push(
+            method,
+            params,
+            json!({ "error": { "code": code, "message": message } }),
+        )
+    }
+
+    /// Expect a `method` notification with exactly `params`
+    pub fn expect_notification(self, method: &str, params: Value) -> Self {
+        let message = json!({ "jsonrpc": "2.0", "method": method, "params": params });
+        self.inner
+            .expected
+            .lock()
+            .unwrap()
+            .push_back((message, None));
+        self
+    }
+
+    fn push(self, method: &str, params: Value, reply: Value) -> Self {
+        let message = json!({ "jsonrpc": "2.0", "method": method, "params": params });
+        self.inner
+            .expected
+            .lock()
+            .unwrap()
+            .push_back((message, Some(reply)));
+        self
+    }
+
+    /// Every message sent so far, in order
+    pub fn sent(&self) -> Vec<Value> {
+        self.inner.sent.lock().unwrap().clone()
+    }
+
+    /// Panic unless every expected message has been sent
+    pub fn assert_done(&self) {
+        let expected = self.inner.expected.lock().unwrap();
+        assert!(
+            expected.is_empty(),
+            "expected messages were never sent: {:?}",
+            expected
+                .iter()
+                .map(|(message, _)| message)
+                .collect::<Vec<_>>()
+        );
+    }
+}
+
+impl Transport for MockTransport {
+    fn send<'a>(&'a self, message: &'a str) -> BoxFuture<'a, Result<(), ClientError>> {
+        Box::pin(async move {
+            let mut message: Value = serde_json::from_str(message)?;
+            self.inner.sent.lock().unwrap().push(message.clone());
+
+            let id = message.as_object_mut().and_then(|m| m.remove("id"));
+            let Some((expected, reply)) = self.inner.expected.lock().unwrap().pop_front() else {
+                panic!("unexpected message: {}", message);
+            };
+            assert_eq!(message, expected, "sent message differs from the script");
+
+            if let (Some(id), Some(mut reply)) = (id, reply) {
+                reply["jsonrpc"] = json!("2.0");
+                reply["id"] = id;
+                self.inner
+                    .replies
+                    .send(reply.to_string())
+                    .map_err(ClientError::transport)?;
+            }
+            Ok(())
+        })
+    }
+
+    fn recv(&self) -> BoxFuture<'_, Result<Option<String>, ClientError>> {
+        Box::pin(async move { Ok(self.inner.incoming.lock().await.recv().await) })
+    }
+
+    fn close(&self) -> BoxFuture<'_, Result<(), ClientError>> {
+        Box::pin(async { Ok(()) })
+    }
+}
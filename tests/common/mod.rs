@@ -0,0 +1,184 @@
+#![allow(dead_code)]
+
+pub mod mock_transport;
+
+use mcp_client_rs::{ClientError, Protocol, DEFAULT_PROTOCOL_VERSION};
+use serde_json::{json, Value};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+/// A scripted MCP server run through `sh`. Each request line is matched on its method and
+/// answered with a canned response template, where `{id}` is replaced by the request id.
+/// Requests for methods without a handler get a JSON-RPC "method not found" error.
+pub struct FakeServer {
+    // (method, shell statements setting `tpl`)
+    handlers: Vec<(String, String)>,
+    record_to: Option<PathBuf>,
+    close_stdin_after: Option<String>,
+    initialize: Value,
+}
+
+impl FakeServer {
+    pub fn new() -> Self {
+        Self {
+            handlers: vec![],
+            record_to: None,
+            close_stdin_after: None,
+            initialize: initialize_result(),
+        }
+    }
+
+    /// Append every line the server receives to `path`
+    pub fn record_to(mut self, path: &Path) -> Self {
+        self.record_to = Some(path.to_path_buf());
+        self
+    }
+
+    /// Answer `method` with a success response carrying `result`
+    pub fn result(self, method: &str, result: Value) -> Self {
+        let template = format!(r#"{{"jsonrpc":"2.0","id":{{id}},"result":{}}}"#, result);
+        self.raw(method, &template)
+    }
+
+    /// Answer `method` with an arbitrary response line
+    pub fn raw(mut self, method: &str, template: &str) -> Self {
+        self.handlers
+            .push((method.to_string(), format!("tpl={}", quote(template))));
+        self
+    }
+
+    /// Answer `method` with `result` after blocking the server for `seconds`
+    pub fn slow(mut self, method: &str, result: Value, seconds: f32) -> Self {
+        let template = format!(r#"{{"jsonrpc":"2.0","id":{{id}},"result":{}}}"#, result);
+        self.handlers.push((
+            method.to_string(),
+            format!("sleep {}; tpl={}", seconds, quote(&template)),
+        ));
+        self
+    }
+
+    /// Answer `method` with `result` after `seconds`, while carrying on with other requests
+    pub fn delayed(mut self, method: &str, result: Value, seconds: f32) -> Self {
+        let template = format!(r#"{{"jsonrpc":"2.0","id":{{id}},"result":{}}}"#, result);
+        self.handlers.push((
+            method.to_string(),
+            format!(
+                "tpl={}; (sleep {}; printf '%s\\n' \"$tpl\" | sed \"s/{{id}}/$id/g\") & continue",
+                quote(&template),
+                seconds
+            ),
+        ));
+        self
+    }
+
+    /// Exit without answering on receiving `method`, like a server crashing
+    pub fn exit_on(mut self, method: &str) -> Self {
+        self.handlers
+            .push((method.to_string(), "exit 1".to_string()));
+        self
+    }
+
+    /// Answer `initialize` with `result` instead of [`initialize_result`]
+    pub fn initialize(mut self, result: Value) -> Self {
+        self.initialize = result;
+        self
+    }
+
+    /// On receiving `method`, stop reading requests, then answer it and keep stdout open for a
+    /// while. The client only sees the answer once stdin is closed.
+    pub fn close_stdin_after(mut self, method: &str) -> Self {
+        self.close_stdin_after = Some(method.to_string());
+        self
+    }
+
+    pub fn script(&self) -> String {
+        let initialize = format!(
+            "tpl={}",
+            quote(&format!(
+                r#"{{"jsonrpc":"2.0","id":{{id}},"result":{}}}"#,
+                self.initialize
+            ))
+        );
+        let not_found =
+            r#"{"jsonrpc":"2.0","id":{id},"error":{"code":-32601,"message":"Method not found"}}"#;
+
+        let mut cases = String::new();
+        for (method, body) in self
+            .handlers
+            .iter()
+            .map(|(m, b)| (m.as_str(), b.as_str()))
+            .chain([("initialize", initialize.as_str())])
+        {
+            cases.push_str(&format!("    *'\"method\":\"{}\"'*) {} ;;\n", method, body));
+        }
+        cases.push_str(&format!("    *) tpl={} ;;\n", quote(not_found)));
+
+        let record = match &self.record_to {
+            Some(path) => format!("printf '%s\\n' \"$line\" >> '{}'", path.display()),
+            None => ":".to_string(),
+        };
+
+        // Stdin is closed before answering so the client can't write in between, and stdout is
+        // held open after answering
+        let (close_stdin, hold_stdout) = match &self.close_stdin_after {
+            Some(method) => (
+                format!(
+                    "case \"$line\" in *'\"method\":\"{}\"'*) exec 0<&- ;; esac",
+                    method
+                ),
+                format!(
+                    "case \"$line\" in *'\"method\":\"{}\"'*) sleep 2 ;; esac",
+                    method
+                ),
+            ),
+            None => (":".to_string(), ":".to_string()),
+        };
+
+        format!(
+            r#"while IFS= read -r line; do
+  {}
+  id=$(printf '%s' "$line" | sed -n 's/^{{\("jsonrpc":"2.0",\)\{{0,1\}}"id":\([0-9]*\).*/\2/p')
+  [ -z "$id" ] && continue
+  case "$line" in
+{}  esac
+  {}
+  printf '%s\n' "$tpl" | sed "s/{{id}}/$id/g"
+  {}
+done
+"#,
+            record, cases, close_stdin, hold_stdout
+        )
+    }
+
+    /// Spawn the script and run the initialize handshake against it
+    pub async fn connect(&self) -> Result<Protocol, ClientError> {
+        Protocol::new(
+            DEFAULT_PROTOCOL_VERSION,
+            "sh",
+            vec!["-c", &self.script()],
+            HashMap::new(),
+        )
+        .await
+    }
+}
+
+// Single-quote `s` for the shell
+fn quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
+
+/// Initialize result advertising tools, resources, prompts and logging
+pub fn initialize_result() -> Value {
+    json!({
+        "protocolVersion": DEFAULT_PROTOCOL_VERSION,
+        "capabilities": {
+            "tools": {},
+            "resources": { "supports_subscribe": true, "supports_delta": false },
+            "prompts": { "supports_custom": false },
+            "logging": { "levels": ["debug", "info", "warning", "error"] }
+        },
+        "serverInfo": { "name": "fake-server", "version": "1.0.0" }
+    })
+}
@@ -0,0 +1,213 @@
+mod common;
+
+use common::FakeServer;
+use mcp_client_rs::{ClientError, Protocol, DEFAULT_PROTOCOL_VERSION};
+use serde_json::json;
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+// A request waiting to be written must not stop an earlier request from receiving its response
+#[tokio::test]
+async fn slow_response_is_delivered_while_next_request_waits() {
+    let client = Arc::new(
+        FakeServer::new()
+            .slow(
+                "tools/call",
+                json!({ "content": [{ "type": "text", "text": "slow result" }] }),
+                0.3,
+            )
+            .result("ping", json!({}))
+            .connect()
+            .await
+            .unwrap(),
+    );
+
+    let slow = tokio::spawn({
+        let client = client.clone();
+        async move { client.call_tool("slow", json!({})).await }
+    });
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    let ping = tokio::spawn({
+        let client = client.clone();
+        async move { client.ping().await }
+    });
+
+    let response = tokio::time::timeout(Duration::from_secs(5), slow)
+        .await
+        .expect("slow request never completed")
+        .unwrap()
+        .unwrap();
+    assert_eq!(response.into_result(), Ok("slow result".to_string()));
+
+    tokio::time::timeout(Duration::from_secs(5), ping)
+        .await
+        .expect("queued request never completed")
+        .unwrap()
+        .unwrap();
+}
+
+// Responses are matched to requests by id, not by arrival order
+#[tokio::test]
+async fn out_of_order_responses_reach_their_requests() {
+    let client = reversing_server().await;
+
+    let (a, b) = tokio::time::timeout(
+        Duration::from_secs(5),
+        futures::future::join(
+            client.call_tool("alpha", json!({})),
+            client.call_tool("beta", json!({})),
+        ),
+    )
+    .await
+    .expect("requests never completed");
+    assert_eq!(a.unwrap().into_result(), Ok("alpha".to_string()));
+    assert_eq!(b.unwrap().into_result(), Ok("beta".to_string()));
+}
+
+#[tokio::test]
+async fn max_in_flight_holds_back_extra_requests() {
+    let script = FakeServer::new()
+        .slow(
+            "tools/call",
+            json!({ "content": [{ "type": "text", "text": "done" }] }),
+            0.2,
+        )
+        .script();
+    let client = Arc::new(
+        Protocol::builder()
+            .program("sh")
+            .args(vec!["-c", &script])
+            .max_in_flight(1)
+            .build()
+            .await
+            .unwrap(),
+    );
+
+    let calls: Vec<_> = (0..2)
+        .map(|_| {
+            let client = client.clone();
+            tokio::spawn(async move { client.call_tool("slow", json!({})).await })
+        })
+        .collect();
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert_eq!(client.in_flight().len(), 1);
+
+    for call in calls {
+        call.await.unwrap().unwrap();
+    }
+}
+
+#[tokio::test]
+async fn many_calls_share_one_connection() {
+    let client = FakeServer::new()
+        .result(
+            "tools/call",
+            json!({ "content": [{ "type": "text", "text": "ok" }] }),
+        )
+        .connect()
+        .await
+        .unwrap();
+
+    let calls = (0..10).map(|_| client.call_tool("echo", json!({})));
+    let responses = tokio::time::timeout(Duration::from_secs(5), futures::future::join_all(calls))
+        .await
+        .expect("calls never completed");
+    for response in responses {
+        assert_eq!(response.unwrap().into_result(), Ok("ok".to_string()));
+    }
+    assert!(client.in_flight().is_empty());
+}
+
+// A server that reads two tool calls and answers them in reverse order, echoing each tool name
+async fn reversing_server() -> Protocol {
+    let script = format!(
+        r#"id_of() {{ printf '%s' "$1" | sed -n 's/.*"id":\([0-9]*\).*/\1/p'; }}
+name_of() {{ printf '%s' "$1" | sed -n 's/.*"name":"\([a-z]*\)".*/\1/p'; }}
+read -r line
+printf '{{"jsonrpc":"2.0","id":%s,"result":%s}}\n' "$(id_of "$line")" '{}'
+read -r initialized
+read -r first
+read -r second
+for line in "$second" "$first"; do
+  printf '{{"jsonrpc":"2.0","id":%s,"result":{{"content":[{{"type":"text","text":"%s"}}]}}}}\n' "$(id_of "$line")" "$(name_of "$line")"
+done
+cat > /dev/null
+"#,
+        common::initialize_result()
+    );
+    Protocol::new(
+        DEFAULT_PROTOCOL_VERSION,
+        "sh",
+        vec!["-c", &script],
+        HashMap::new(),
+    )
+    .await
+    .unwrap()
+}
+
+#[tokio::test]
+async fn cloned_handles_share_the_connection() {
+    let client = reversing_server().await;
+    let handle = client.clone_handle();
+
+    let (a, b) = tokio::time::timeout(
+        Duration::from_secs(5),
+        futures::future::join(
+            client.call_tool("alpha", json!({})),
+            handle.call_tool("beta", json!({})),
+        ),
+    )
+    .await
+    .expect("requests never completed");
+    assert_eq!(a.unwrap().into_result(), Ok("alpha".to_string()));
+    assert_eq!(b.unwrap().into_result(), Ok("beta".to_string()));
+}
+
+#[tokio::test]
+async fn max_in_flight_of_zero_still_sends_requests() {
+    let script = FakeServer::new()
+        .result("tools/list", json!({ "tools": [] }))
+        .script();
+    let client = Protocol::builder()
+        .program("sh")
+        .args(vec!["-c", &script])
+        .max_in_flight(0)
+        .request_timeout(Duration::from_secs(2))
+        .build()
+        .await
+        .unwrap();
+
+    client.list_tools().await.unwrap();
+}
+
+#[tokio::test]
+async fn waiting_for_a_slot_counts_toward_the_timeout() {
+    let script = FakeServer::new()
+        .delayed(
+            "tools/call",
+            json!({ "content": [{ "type": "text", "text": "done" }] }),
+            1.0,
+        )
+        .script();
+    let client = Protocol::builder()
+        .program("sh")
+        .args(vec!["-c", &script])
+        .max_in_flight(1)
+        .request_timeout(Duration::from_millis(300))
+        .build()
+        .await
+        .unwrap();
+
+    let started = std::time::Instant::now();
+    let (first, second) = tokio::join!(
+        client.call_tool("slow", json!({})),
+        client.call_tool("slow", json!({}))
+    );
+    assert!(matches!(first, Err(ClientError::Timeout)));
+    assert!(matches!(second, Err(ClientError::Timeout)));
+    // The second call gives up while still waiting for the first one's slot
+    assert!(
+        started.elapsed() < Duration::from_millis(550),
+        "took {:?}",
+        started.elapsed()
+    );
+}
@@ -0,0 +1,61 @@
+mod common;
+
+use common::FakeServer;
+use mcp_client_rs::ClientError;
+use serde_json::json;
+use std::time::{Duration, Instant};
+
+#[tokio::test]
+async fn calls_past_the_deadline_time_out() {
+    let client = FakeServer::new()
+        .slow(
+            "tools/call",
+            json!({ "content": [{ "type": "text", "text": "slow result" }] }),
+            0.5,
+        )
+        .result("tools/list", json!({ "tools": [] }))
+        .connect()
+        .await
+        .unwrap();
+
+    let scoped = client.with_deadline(Instant::now() + Duration::from_millis(100));
+    assert!(matches!(
+        scoped.call_tool("slow", json!({})).await,
+        Err(ClientError::Timeout)
+    ));
+    assert!(matches!(
+        scoped.list_tools().await,
+        Err(ClientError::Timeout)
+    ));
+
+    // The late response to the timed-out call must not be taken as this request's response
+    let tools = client.list_tools().await.unwrap();
+    assert!(tools.tools.is_empty());
+}
+
+#[tokio::test]
+async fn requests_time_out_after_the_request_timeout() {
+    let mut client = FakeServer::new()
+        .slow(
+            "tools/call",
+            json!({ "content": [{ "type": "text", "text": "slow result" }] }),
+            0.5,
+        )
+        .connect()
+        .await
+        .unwrap();
+    client.set_request_timeout(Duration::from_millis(100));
+
+    assert!(matches!(
+        client.call_tool("slow", json!({})).await,
+        Err(ClientError::Timeout)
+    ));
+    assert!(client.in_flight().is_empty());
+
+    // A per-call timeout overrides the client's
+    let response = client
+        .call_tool_with_timeout("slow", json!({}), Duration::from_secs(5))
+        .await
+        .unwrap();
+    assert_eq!(response.into_result(), Ok("slow result".to_string()));
+}
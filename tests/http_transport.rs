@@ -0,0 +1,301 @@
+#![cfg(feature = "http")]
+
+mod common;
+
+use common::initialize_result;
+use flate2::{write::GzEncoder, Compression};
+use mcp_client_rs::{HttpTransport, Protocol, ServerNotification};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+
+// Method and headers (with lowercase names) of each request received, in order
+type Requests = Arc<Mutex<Vec<(String, HashMap<String, String>)>>>;
+
+// Minimal streamable HTTP server: answers initialize with a JSON body and a session id,
+// tools/list with an event stream carrying a notification before the response, resources/read
+// with a gzip-encoded body if the client accepts one, and acknowledges everything else. A GET
+// gets an event stream carrying a notification if `event_stream` is set, otherwise a 405.
+async fn serve(listener: TcpListener, requests: Requests, event_stream: bool) {
+    loop {
+        let Ok((stream, _)) = listener.accept().await else {
+            return;
+        };
+        tokio::spawn(handle(stream, requests.clone(), event_stream));
+    }
+}
+
+async fn handle(stream: TcpStream, requests: Requests, event_stream: bool) {
+    let mut stream = BufReader::new(stream);
+    let mut headers = HashMap::new();
+    let mut line = String::new();
+    stream.read_line(&mut line).await.unwrap();
+    let method = line.split(' ').next().unwrap().to_string();
+    let is_get = method == "GET";
+    loop {
+        line.clear();
+        stream.read_line(&mut line).await.unwrap();
+        let Some((name, value)) = line.trim_end().split_once(':') else {
+            break;
+        };
+        headers.insert(name.to_lowercase(), value.trim().to_string());
+    }
+    let length: usize = headers
+        .get("content-length")
+        .map_or(0, |l| l.parse().unwrap());
+    let mut body = vec![0; length];
+    stream.read_exact(&mut body).await.unwrap();
+    requests.lock().unwrap().push((method, headers.clone()));
+
+    if is_get {
+        let stream = stream.get_mut();
+        if !event_stream {
+            stream
+                .write_all(
+                    b"HTTP/1.1 405 Method Not Allowed\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                )
+                .await
+                .unwrap();
+            return;
+        }
+        let head =
+            "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nConnection: close\r\n\r\n";
+        stream.write_all(head.as_bytes()).await.unwrap();
+        stream.flush().await.unwrap();
+        // Give the client time to subscribe to notifications
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        let notification = json!({ "jsonrpc": "2.0", "method": "notifications/message", "params": { "level": "info", "data": "pushed" } });
+        stream
+            .write_all(format!("data: {}\n\n", notification).as_bytes())
+            .await
+            .unwrap();
+        return;
+    }
+
+    let request: Value = serde_json::from_slice(&body).unwrap();
+    let stream = stream.get_mut();
+    match request["method"].as_str().unwrap() {
+        "initialize" => {
+            let body =
+                json!({ "jsonrpc": "2.0", "id": request["id"], "result": initialize_result() })
+                    .to_string();
+            let head = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nMcp-Session-Id: session-1\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                body.len()
+            );
+            stream.write_all(head.as_bytes()).await.unwrap();
+            stream.write_all(body.as_bytes()).await.unwrap();
+        }
+        "tools/list" => {
+            let head =
+                "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nConnection: close\r\n\r\n";
+            stream.write_all(head.as_bytes()).await.unwrap();
+            let notification = json!({ "jsonrpc": "2.0", "method": "notifications/message", "params": { "level": "info", "data": "listing" } });
+            let response = json!({ "jsonrpc": "2.0", "id": request["id"], "result": { "tools": [
+                { "name": "echo", "description": "Echo input", "inputSchema": {} }
+            ] } })
+            .to_string();
+            let (first, second) = response.split_at(response.len() / 2);
+            stream
+                .write_all(format!(": keepalive\n\ndata: {}\n\n", notification).as_bytes())
+                .await
+                .unwrap();
+            // Split an event across writes to exercise incremental parsing
+            stream
+                .write_all(format!("event: message\ndata: {}", first).as_bytes())
+                .await
+                .unwrap();
+            stream.flush().await.unwrap();
+            stream
+                .write_all(format!("{}\r\n\r\n", second).as_bytes())
+                .await
+                .unwrap();
+        }
+        "resources/read" => {
+            let gzip = headers
+                .get("accept-encoding")
+                .is_some_and(|encodings| encodings.contains("gzip"));
+            let text = if gzip { "compressed" } else { "plain" };
+            let body = json!({ "jsonrpc": "2.0", "id": request["id"], "result": { "contents": [
+                { "uri": request["params"]["uri"], "text": text }
+            ] } })
+            .to_string();
+            let (encoding, body) = if gzip {
+                let mut encoder = GzEncoder::new(vec![], Compression::default());
+                encoder.write_all(body.as_bytes()).unwrap();
+                ("Content-Encoding: gzip\r\n", encoder.finish().unwrap())
+            } else {
+                ("", body.into_bytes())
+            };
+            let head = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\n{}Content-Length: {}\r\nConnection: close\r\n\r\n",
+                encoding,
+                body.len()
+            );
+            stream.write_all(head.as_bytes()).await.unwrap();
+            stream.write_all(&body).await.unwrap();
+        }
+        _ => {
+            stream
+                .write_all(
+                    b"HTTP/1.1 202 Accepted\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                )
+                .await
+                .unwrap();
+        }
+    }
+}
+
+#[tokio::test]
+async fn talks_streamable_http() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/mcp", listener.local_addr().unwrap());
+    let requests = Requests::default();
+    tokio::spawn(serve(listener, requests.clone(), false));
+
+    let transport = HttpTransport::new(&url, HashMap::new()).unwrap();
+    let client = Protocol::builder()
+        .transport(transport)
+        .build()
+        .await
+        .unwrap();
+    assert_eq!(client.server_info().unwrap().name, "fake-server");
+
+    let tools = client.list_tools().await.unwrap();
+    assert_eq!(tools.tools[0].name, "echo");
+
+    // The session id from initialization is sent on later requests
+    let sessions: Vec<Option<String>> = requests
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|(method, _)| method == "POST")
+        .map(|(_, headers)| headers.get("mcp-session-id").cloned())
+        .collect();
+    assert_eq!(
+        sessions,
+        [
+            None,
+            Some("session-1".to_string()),
+            Some("session-1".to_string())
+        ]
+    );
+}
+
+async fn read_text(transport: HttpTransport) -> String {
+    let client = Protocol::builder()
+        .transport(transport)
+        .build()
+        .await
+        .unwrap();
+    let response = client.read_resource("file:///big.txt").await.unwrap();
+    response.contents[0].text.clone().unwrap()
+}
+
+#[tokio::test]
+async fn decompresses_gzip_responses() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/mcp", listener.local_addr().unwrap());
+    tokio::spawn(serve(listener, Requests::default(), false));
+
+    let transport = HttpTransport::new(&url, HashMap::new()).unwrap();
+    assert_eq!(read_text(transport).await, "compressed");
+
+    // Without compression the server isn't offered gzip
+    let transport = HttpTransport::new(&url, HashMap::new())
+        .unwrap()
+        .compression(false);
+    assert_eq!(read_text(transport).await, "plain");
+}
+
+#[tokio::test]
+async fn sends_the_user_agent() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/mcp", listener.local_addr().unwrap());
+    let requests = Requests::default();
+    tokio::spawn(serve(listener, requests.clone(), false));
+
+    let transport = HttpTransport::new(&url, HashMap::new())
+        .unwrap()
+        .user_agent("my-app/2.0")
+        .unwrap();
+    let client = Protocol::builder()
+        .transport(transport)
+        .build()
+        .await
+        .unwrap();
+    client.list_tools().await.unwrap();
+
+    let user_agents: Vec<Option<String>> = requests
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|(method, _)| method == "POST")
+        .map(|(_, headers)| headers.get("user-agent").cloned())
+        .collect();
+    assert_eq!(user_agents, vec![Some("my-app/2.0".to_string()); 3]);
+}
+
+#[tokio::test]
+async fn user_agent_defaults_to_the_crate_version() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/mcp", listener.local_addr().unwrap());
+    let requests = Requests::default();
+    tokio::spawn(serve(listener, requests.clone(), false));
+
+    let transport = HttpTransport::new(&url, HashMap::new()).unwrap();
+    Protocol::builder()
+        .transport(transport)
+        .build()
+        .await
+        .unwrap();
+
+    assert_eq!(
+        requests.lock().unwrap()[0]
+            .1
+            .get("user-agent")
+            .map(String::as_str),
+        Some(concat!("mcp-client-rs/", env!("CARGO_PKG_VERSION")))
+    );
+}
+
+#[tokio::test]
+async fn receives_notifications_over_the_event_stream() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/mcp", listener.local_addr().unwrap());
+    let requests = Requests::default();
+    tokio::spawn(serve(listener, requests.clone(), true));
+
+    let transport = HttpTransport::new(&url, HashMap::new()).unwrap();
+    let client = Protocol::builder()
+        .transport(transport)
+        .build()
+        .await
+        .unwrap();
+    let mut notifications = client.notifications();
+
+    let notification = tokio::time::timeout(Duration::from_secs(2), notifications.recv())
+        .await
+        .expect("no notification over the event stream")
+        .unwrap();
+    assert!(matches!(
+        notification,
+        ServerNotification::Message { data, .. } if data == "pushed"
+    ));
+
+    // The stream is opened within the session, asking for events
+    let requests = requests.lock().unwrap();
+    let (_, get) = requests.iter().find(|(method, _)| method == "GET").unwrap();
+    assert_eq!(
+        get.get("accept").map(String::as_str),
+        Some("text/event-stream")
+    );
+    assert_eq!(
+        get.get("mcp-session-id").map(String::as_str),
+        Some("session-1")
+    );
+}
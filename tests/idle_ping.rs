@@ -0,0 +1,53 @@
+mod common;
+
+use common::FakeServer;
+use mcp_client_rs::{ClientError, ConnectionState, Protocol};
+use serde_json::json;
+use std::time::Duration;
+
+#[tokio::test]
+async fn unanswered_idle_ping_closes_the_connection() {
+    // The server hangs on ping for longer than the request timeout
+    let script = FakeServer::new().slow("ping", json!({}), 10.0).script();
+    let client = Protocol::builder()
+        .program("sh")
+        .args(vec!["-c", &script])
+        .request_timeout(Duration::from_millis(200))
+        .idle_ping_interval(Duration::from_millis(50))
+        .build()
+        .await
+        .unwrap();
+    let mut states = client.watch_connection_state();
+
+    tokio::time::timeout(
+        Duration::from_secs(2),
+        states.wait_for(|state| *state == ConnectionState::Closed),
+    )
+    .await
+    .expect("the connection was not closed")
+    .unwrap();
+    assert!(matches!(
+        client.list_tools().await,
+        Err(ClientError::ServerClosed { .. })
+    ));
+}
+
+#[tokio::test]
+async fn idle_pings_update_the_latency() {
+    let script = FakeServer::new().result("ping", json!({})).script();
+    let client = Protocol::builder()
+        .program("sh")
+        .args(vec!["-c", &script])
+        .idle_ping_interval(Duration::from_millis(50))
+        .build()
+        .await
+        .unwrap();
+
+    for _ in 0..100 {
+        if client.latency().is_some() {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    panic!("no idle ping was measured");
+}
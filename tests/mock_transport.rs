@@ -0,0 +1,78 @@
+mod common;
+
+use common::{initialize_result, mock_transport::MockTransport};
+use mcp_client_rs::{ClientError, Protocol};
+use serde_json::json;
+
+#[tokio::test]
+async fn drives_a_session_from_a_script() {
+    let mock = MockTransport::new()
+        .handshake()
+        .expect(
+            "tools/list",
+            json!({}),
+            json!({ "tools": [{ "name": "echo", "description": "Echo input", "inputSchema": {} }] }),
+        )
+        .expect(
+            "tools/call",
+            json!({ "name": "echo", "arguments": { "text": "hi" } }),
+            json!({ "content": [{ "type": "text", "text": "hi" }] }),
+        );
+    let client = Protocol::builder()
+        .transport(mock.clone())
+        .build()
+        .await
+        .unwrap();
+
+    let tools = client.list_tools().await.unwrap();
+    assert_eq!(tools.tools[0].name, "echo");
+    let response = client
+        .call_tool("echo", json!({ "text": "hi" }))
+        .await
+        .unwrap();
+    assert_eq!(response.into_result(), Ok("hi".to_string()));
+    mock.assert_done();
+}
+
+#[tokio::test]
+async fn missing_capability_fails_without_a_request() {
+    let mut initialize = initialize_result();
+    initialize["capabilities"] = json!({});
+    let mock = MockTransport::new().handshake_with(initialize);
+    let client = Protocol::builder()
+        .transport(mock.clone())
+        .build()
+        .await
+        .unwrap();
+
+    assert!(matches!(
+        client.list_tools().await,
+        Err(ClientError::CapabilityError(_))
+    ));
+    // Only the handshake went out
+    assert_eq!(mock.sent().len(), 2);
+}
+
+#[tokio::test]
+async fn error_replies_surface_as_rpc_errors() {
+    let mock = MockTransport::new().handshake().expect_error(
+        "resources/read",
+        json!({ "uri": "file:///missing" }),
+        -32002,
+        "Resource not found",
+    );
+    let client = Protocol::builder()
+        .transport(mock.clone())
+        .build()
+        .await
+        .unwrap();
+
+    match client.read_resource("file:///missing").await {
+        Err(ClientError::Rpc { code, message, .. }) => {
+            assert_eq!(code, -32002);
+            assert_eq!(message, "Resource not found");
+        }
+        other => panic!("expected an RPC error, got {:?}", other),
+    }
+    mock.assert_done();
+}
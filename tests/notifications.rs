@@ -0,0 +1,90 @@
+mod common;
+
+use common::FakeServer;
+use mcp_client_rs::{LogLevel, LogSource, ProgressUpdate, ServerNotification};
+use serde_json::json;
+use std::sync::Mutex;
+
+#[tokio::test]
+async fn server_notifications_reach_subscribers() {
+    // The server pushes notifications just before answering tools/list
+    let client = FakeServer::new()
+        .raw(
+            "tools/list",
+            "{\"jsonrpc\":\"2.0\",\"method\":\"notifications/resources/updated\",\"params\":{\"uri\":\"file:///a.txt\"}}\n\
+             {\"jsonrpc\":\"2.0\",\"method\":\"notifications/message\",\"params\":{\"level\":\"warning\",\"logger\":\"db\",\"data\":\"slow query\"}}\n\
+             {\"jsonrpc\":\"2.0\",\"method\":\"notifications/custom\",\"params\":{\"n\":1}}\n\
+             {\"jsonrpc\":\"2.0\",\"id\":{id},\"result\":{\"tools\":[]}}",
+        )
+        .connect()
+        .await
+        .unwrap();
+    let mut notifications = client.notifications();
+
+    client.list_tools().await.unwrap();
+
+    assert!(matches!(
+        notifications.recv().await.unwrap(),
+        ServerNotification::ResourceUpdated { uri } if uri == "file:///a.txt"
+    ));
+    assert!(matches!(
+        notifications.recv().await.unwrap(),
+        ServerNotification::Message {
+            level: LogLevel::Warning,
+            ..
+        }
+    ));
+    match notifications.recv().await.unwrap() {
+        ServerNotification::Other { method, params } => {
+            assert_eq!(method, "notifications/custom");
+            assert_eq!(params, json!({ "n": 1 }));
+        }
+        other => panic!("expected an unrecognized notification, got {:?}", other),
+    }
+
+    let logs = client.recent_logs(LogLevel::Warning, 10);
+    assert_eq!(logs.len(), 1);
+    assert_eq!(logs[0].source, LogSource::Notification);
+    assert_eq!(logs[0].message, "db: slow query");
+}
+
+#[tokio::test]
+async fn progress_for_a_tool_call_reaches_its_callback() {
+    // Progress for another request's token is ignored
+    let client = FakeServer::new()
+        .raw(
+            "tools/call",
+            "{\"jsonrpc\":\"2.0\",\"method\":\"notifications/progress\",\"params\":{\"progressToken\":{id},\"progress\":1,\"total\":2,\"message\":\"halfway\"}}\n\
+             {\"jsonrpc\":\"2.0\",\"method\":\"notifications/progress\",\"params\":{\"progressToken\":\"other\",\"progress\":5}}\n\
+             {\"jsonrpc\":\"2.0\",\"method\":\"notifications/progress\",\"params\":{\"progressToken\":{id},\"progress\":2,\"total\":2}}\n\
+             {\"jsonrpc\":\"2.0\",\"id\":{id},\"result\":{\"content\":[{\"type\":\"text\",\"text\":\"done\"}]}}",
+        )
+        .connect()
+        .await
+        .unwrap();
+
+    let updates = Mutex::new(vec![]);
+    let response = client
+        .call_tool_with_progress("index", json!({}), |update| {
+            updates.lock().unwrap().push(update)
+        })
+        .await
+        .unwrap();
+    assert_eq!(response.into_result(), Ok("done".to_string()));
+
+    assert_eq!(
+        updates.into_inner().unwrap(),
+        [
+            ProgressUpdate {
+                progress: 1.0,
+                total: Some(2.0),
+                message: Some("halfway".to_string()),
+            },
+            ProgressUpdate {
+                progress: 2.0,
+                total: Some(2.0),
+                message: None,
+            },
+        ]
+    );
+}
@@ -0,0 +1,120 @@
+mod common;
+
+use common::FakeServer;
+use mcp_client_rs::ClientError;
+use serde_json::{json, Value};
+
+// Handlers match on the request line, so the page a cursor asks for is told apart by matching
+// the params along with the method. They must be registered before the cursor-less handler.
+fn page(method: &str, cursor: &str) -> String {
+    format!(r#"{}","params":{{"cursor":"{}"#, method, cursor)
+}
+
+fn tool(name: &str) -> Value {
+    json!({ "name": name, "description": "", "inputSchema": { "type": "object" } })
+}
+
+#[tokio::test]
+async fn list_all_tools_follows_the_cursor() {
+    let client = FakeServer::new()
+        .result(
+            &page("tools/list", "page-2"),
+            json!({ "tools": [tool("c")] }),
+        )
+        .result(
+            "tools/list",
+            json!({ "tools": [tool("a"), tool("b")], "nextCursor": "page-2" }),
+        )
+        .connect()
+        .await
+        .unwrap();
+
+    let first = client.list_tools().await.unwrap();
+    assert_eq!(first.tools.len(), 2);
+    assert_eq!(first.next_cursor.as_deref(), Some("page-2"));
+
+    let names: Vec<_> = client
+        .list_all_tools()
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|tool| tool.name)
+        .collect();
+    assert_eq!(names, ["a", "b", "c"]);
+}
+
+#[tokio::test]
+async fn list_all_resources_follows_the_cursor() {
+    let client = FakeServer::new()
+        .result(
+            &page("resources/list", "page-3"),
+            json!({ "resources": [{ "uri": "file:///c", "type": "text" }] }),
+        )
+        .result(
+            &page("resources/list", "page-2"),
+            json!({
+                "resources": [{ "uri": "file:///b", "type": "text" }],
+                "nextCursor": "page-3"
+            }),
+        )
+        .result(
+            "resources/list",
+            json!({
+                "resources": [{ "uri": "file:///a", "type": "text" }],
+                "nextCursor": "page-2"
+            }),
+        )
+        .connect()
+        .await
+        .unwrap();
+
+    let uris: Vec<_> = client
+        .list_all_resources()
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|resource| resource.uri)
+        .collect();
+    assert_eq!(uris, ["file:///a", "file:///b", "file:///c"]);
+}
+
+#[tokio::test]
+async fn repeated_cursor_is_an_error() {
+    let client = FakeServer::new()
+        .result(
+            "prompts/list",
+            json!({ "prompts": [], "nextCursor": "again" }),
+        )
+        .connect()
+        .await
+        .unwrap();
+
+    let err = client.list_all_prompts().await.unwrap_err();
+    assert!(matches!(err, ClientError::ProtocolError(_)), "{:?}", err);
+}
+
+#[tokio::test]
+async fn list_resources_sends_the_cursor() {
+    let client = FakeServer::new()
+        .result(
+            &page("resources/list", "page-2"),
+            json!({ "resources": [{ "uri": "file:///b", "type": "text" }] }),
+        )
+        .result(
+            "resources/list",
+            json!({
+                "resources": [{ "uri": "file:///a", "type": "text" }],
+                "nextCursor": "page-2"
+            }),
+        )
+        .connect()
+        .await
+        .unwrap();
+
+    let first = client.list_resources(None).await.unwrap();
+    assert_eq!(first.resources[0].uri, "file:///a");
+
+    let second = client.list_resources(first.next_cursor).await.unwrap();
+    assert_eq!(second.resources[0].uri, "file:///b");
+    assert!(second.next_cursor.is_none());
+}
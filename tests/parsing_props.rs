@@ -0,0 +1,141 @@
+mod common;
+
+use common::FakeServer;
+use mcp_client_rs::ClientError;
+use proptest::prelude::*;
+use serde_json::{json, Map, Value};
+use std::time::{Duration, Instant};
+
+// Arbitrary JSON, kept small so each case stays a single short line
+fn any_json() -> impl Strategy<Value = Value> {
+    let leaf = prop_oneof![
+        Just(Value::Null),
+        any::<bool>().prop_map(Value::from),
+        any::<i64>().prop_map(Value::from),
+        any::<f64>()
+            .prop_filter("finite", |f| f.is_finite())
+            .prop_map(Value::from),
+        ".{0,12}".prop_map(Value::from),
+    ];
+    leaf.prop_recursive(3, 24, 4, |inner| {
+        prop_oneof![
+            prop::collection::vec(inner.clone(), 0..4).prop_map(Value::from),
+            prop::collection::btree_map("[a-zA-Z_]{1,8}", inner, 0..4)
+                .prop_map(|m| Value::Object(m.into_iter().collect())),
+        ]
+    })
+}
+
+// A content item that is sometimes valid, sometimes missing fields or of an unknown type
+fn content_item() -> impl Strategy<Value = Value> {
+    let kind = prop_oneof![
+        Just("text"),
+        Just("image"),
+        Just("resource"),
+        Just("resource_link"),
+        Just("audio"),
+    ];
+    (
+        kind,
+        prop::option::of(any_json()),
+        prop::option::of(any_json()),
+    )
+        .prop_map(|(kind, text, extra)| {
+            let mut item = Map::new();
+            item.insert("type".into(), kind.into());
+            if let Some(text) = text {
+                item.insert("text".into(), text);
+            }
+            if let Some(extra) = extra {
+                item.insert("uri".into(), extra);
+            }
+            Value::Object(item)
+        })
+}
+
+fn result_body() -> impl Strategy<Value = Value> {
+    prop_oneof![
+        any_json(),
+        prop::collection::vec(content_item(), 0..4)
+            .prop_map(|content| json!({ "content": content })),
+        (prop::collection::vec(content_item(), 0..3), any_json())
+            .prop_map(|(content, is_error)| json!({ "content": content, "isError": is_error })),
+    ]
+}
+
+// A response frame for the request, with the id placeholder `{id}` substituted by the server
+fn frame() -> impl Strategy<Value = String> {
+    let id = prop_oneof![
+        Just(Some("{id}".to_string())),
+        Just(Some("null".to_string())),
+        Just(None),
+        any::<u32>().prop_map(|n| Some(n.to_string())),
+        Just(Some(r#""abc""#.to_string())),
+    ];
+    let body = prop_oneof![
+        result_body().prop_map(|r| format!(r#""result":{}"#, r)),
+        (any_json(), any_json()).prop_map(|(code, message)| format!(
+            r#""error":{{"code":{},"message":{}}}"#,
+            code, message
+        )),
+        Just(String::new()),
+    ];
+    (id, body, any::<bool>(), any::<bool>()).prop_map(|(id, body, batch, notification)| {
+        let mut fields = vec![r#""jsonrpc":"2.0""#.to_string()];
+        if let Some(id) = id {
+            fields.push(format!(r#""id":{}"#, id));
+        }
+        if notification {
+            fields.push(r#""method":"notifications/message""#.to_string());
+        }
+        if !body.is_empty() {
+            fields.push(body);
+        }
+        let frame = format!("{{{}}}", fields.join(","));
+        if batch {
+            format!("[{}]", frame)
+        } else {
+            frame
+        }
+    })
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(48))]
+
+    #[test]
+    fn tool_call_response_parsing_never_panics(frame in frame()) {
+        // Frames with a method are server requests or notifications, not the response
+        let answers_the_call = frame.contains(r#""id":{id}"#) && !frame.contains(r#""method""#);
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let (call, ping) = runtime.block_on(async {
+            let client = FakeServer::new()
+                .raw("tools/call", &frame)
+                .result("ping", json!({}))
+                .connect()
+                .await
+                .unwrap();
+            // Any Ok or Err is acceptable. Frames that don't answer the request (another id, a
+            // notification) leave it waiting until the deadline.
+            let call = client
+                .with_deadline(Instant::now() + Duration::from_millis(300))
+                .call_tool("echo", json!({}))
+                .await;
+            // Parsing runs on the reader task, where a panic would only show as the connection
+            // no longer answering
+            let ping = client
+                .with_deadline(Instant::now() + Duration::from_secs(2))
+                .ping()
+                .await;
+            (call, ping)
+        });
+        prop_assert!(ping.is_ok(), "connection broken after {}: {:?}", frame, ping);
+        if answers_the_call {
+            prop_assert!(
+                !matches!(call, Err(ClientError::Timeout)),
+                "{} was never delivered to the call",
+                frame
+            );
+        }
+    }
+}
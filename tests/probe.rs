@@ -0,0 +1,40 @@
+mod common;
+
+use common::{initialize_result, FakeServer};
+use mcp_client_rs::RequestType;
+use serde_json::json;
+
+#[tokio::test]
+async fn probe_reports_method_not_found_as_unsupported() {
+    let client = FakeServer::new()
+        .result("ping", json!({}))
+        .result("tools/list", json!({ "tools": [] }))
+        .connect()
+        .await
+        .unwrap();
+
+    let support = client.probe_methods().await;
+    assert!(support[&RequestType::Ping]);
+    assert!(support[&RequestType::ListTools]);
+    assert!(!support[&RequestType::PromptsList]);
+    // Judged from the advertised tools capability rather than by calling a tool
+    assert!(support[&RequestType::CallTool]);
+
+    assert_eq!(
+        client.method_supported(&RequestType::PromptsList),
+        Some(false)
+    );
+    assert_eq!(client.method_supported(&RequestType::Initialize), None);
+}
+
+#[tokio::test]
+async fn probe_needs_supports_subscribe_for_subscriptions() {
+    let mut init = initialize_result();
+    init["capabilities"]["resources"]["supports_subscribe"] = json!(false);
+    let client = FakeServer::new().initialize(init).connect().await.unwrap();
+
+    let support = client.probe_methods().await;
+    assert!(support[&RequestType::ResourcesRead]);
+    assert!(!support[&RequestType::ResourcesSubscribe]);
+    assert!(!support[&RequestType::ResourcesUnsubscribe]);
+}
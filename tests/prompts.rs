@@ -0,0 +1,160 @@
+mod common;
+
+use common::{initialize_result, FakeServer};
+use futures::StreamExt;
+use mcp_client_rs::{PromptMessage, Role, ToolResponseContent};
+use serde_json::{json, Value};
+
+#[tokio::test]
+async fn get_prompt_sends_name_and_arguments() {
+    let path = std::env::temp_dir().join(format!("mcp-get-prompt-{}.jsonl", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    let client = FakeServer::new()
+        .record_to(&path)
+        .result(
+            "prompts/get",
+            json!({
+                "description": "Review code",
+                "messages": [{ "role": "user", "content": { "type": "text", "text": "Review fn main() {}" } }]
+            }),
+        )
+        .connect()
+        .await
+        .unwrap();
+
+    let prompt = client
+        .get_prompt("review", json!({ "code": "fn main() {}" }))
+        .await
+        .unwrap();
+    assert_eq!(prompt.description.as_deref(), Some("Review code"));
+    assert_eq!(prompt.messages[0].role, Role::User);
+    assert!(matches!(
+        &prompt.messages[0].content,
+        ToolResponseContent::Text { text, .. } if text == "Review fn main() {}"
+    ));
+
+    let recorded = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    let request: Value = serde_json::from_str(recorded.lines().last().unwrap()).unwrap();
+    assert_eq!(request["method"], "prompts/get");
+    assert_eq!(
+        request["params"],
+        json!({ "name": "review", "arguments": { "code": "fn main() {}" } })
+    );
+}
+
+fn texts(messages: Vec<PromptMessage>) -> Vec<String> {
+    messages
+        .into_iter()
+        .map(|message| match message.content {
+            ToolResponseContent::Text { text, .. } => text,
+            other => panic!("expected text, got {:?}", other),
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn get_prompt_stream_falls_back_to_a_single_request() {
+    let path = std::env::temp_dir().join(format!("mcp-prompt-stream-{}.jsonl", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    let client = FakeServer::new()
+        .record_to(&path)
+        .result(
+            "prompts/get",
+            json!({
+                "messages": [
+                    { "role": "user", "content": { "type": "text", "text": "first" } },
+                    { "role": "assistant", "content": { "type": "text", "text": "second" } }
+                ]
+            }),
+        )
+        .connect()
+        .await
+        .unwrap();
+
+    let messages: Vec<PromptMessage> = client
+        .get_prompt_stream("review", json!({}))
+        .map(Result::unwrap)
+        .collect()
+        .await;
+    assert_eq!(texts(messages), ["first", "second"]);
+
+    // Without the streaming capability the request carries no progress token
+    let recorded = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    let request: Value = serde_json::from_str(recorded.lines().last().unwrap()).unwrap();
+    assert_eq!(
+        request["params"],
+        json!({ "name": "review", "arguments": {} })
+    );
+}
+
+#[tokio::test]
+async fn get_prompt_stream_fallback_ends_on_error() {
+    let client = FakeServer::new()
+        .raw(
+            "prompts/get",
+            r#"{"jsonrpc":"2.0","id":{id},"error":{"code":-32602,"message":"Unknown prompt"}}"#,
+        )
+        .connect()
+        .await
+        .unwrap();
+
+    let results: Vec<_> = client
+        .get_prompt_stream("missing", json!({}))
+        .collect()
+        .await;
+    assert_eq!(results.len(), 1);
+    assert!(results[0].is_err());
+}
+
+#[tokio::test]
+async fn get_prompt_stream_yields_streamed_messages() {
+    let mut init = initialize_result();
+    init["capabilities"]["prompts"]["supports_streaming"] = json!(true);
+    let client = FakeServer::new()
+        .initialize(init)
+        .raw(
+            "prompts/get",
+            r#"{"jsonrpc":"2.0","method":"notifications/prompts/message","params":{"progressToken":{id},"message":{"role":"user","content":{"type":"text","text":"first"}}}}
+{"jsonrpc":"2.0","method":"notifications/prompts/message","params":{"progressToken":999,"message":{"role":"user","content":{"type":"text","text":"someone else's"}}}}
+{"jsonrpc":"2.0","id":{id},"result":{"messages":[{"role":"user","content":{"type":"text","text":"first"}},{"role":"assistant","content":{"type":"text","text":"second"}}]}}"#,
+        )
+        .connect()
+        .await
+        .unwrap();
+
+    let messages: Vec<PromptMessage> = client
+        .get_prompt_stream("review", json!({}))
+        .map(Result::unwrap)
+        .collect()
+        .await;
+    // The streamed message isn't repeated from the final response
+    assert_eq!(texts(messages), ["first", "second"]);
+}
+
+#[tokio::test]
+async fn complete_returns_suggestions() {
+    let client = FakeServer::new()
+        .result(
+            "completion/complete",
+            json!({ "completion": { "values": ["python", "pytorch"], "total": 10, "hasMore": true } }),
+        )
+        .connect()
+        .await
+        .unwrap();
+
+    let completion = client
+        .complete(
+            json!({ "type": "ref/prompt", "name": "code_review" }),
+            "language",
+            "py",
+        )
+        .await
+        .unwrap();
+    assert_eq!(completion.values, ["python", "pytorch"]);
+    assert_eq!(completion.total, Some(10));
+    assert_eq!(completion.has_more, Some(true));
+}
@@ -0,0 +1,289 @@
+mod common;
+
+use common::{initialize_result, FakeServer};
+use mcp_client_rs::{ClientError, ProtocolManager};
+use serde_json::json;
+use std::collections::HashMap;
+
+// A server reporting `name` as its server name, with a tool of its own and one every server
+// has. Tool calls answer with the server's name.
+fn named_server(name: &str) -> FakeServer {
+    let mut initialize = initialize_result();
+    initialize["serverInfo"]["name"] = json!(name);
+    FakeServer::new()
+        .initialize(initialize)
+        .result(
+            "tools/list",
+            json!({ "tools": [
+                { "name": format!("{}_tool", name), "description": "", "inputSchema": {} },
+                { "name": "shared", "description": "", "inputSchema": {} }
+            ] }),
+        )
+        .result(
+            "tools/call",
+            json!({ "content": [{ "type": "text", "text": name }] }),
+        )
+}
+
+// Start a manager from a config listing each server under its name
+async fn manager(servers: &[(&str, FakeServer)], label: &str) -> ProtocolManager {
+    let config = std::env::temp_dir().join(format!("mcp-{}-{}.json", label, std::process::id()));
+    let servers: serde_json::Map<_, _> = servers
+        .iter()
+        .map(|(name, server)| {
+            (
+                name.to_string(),
+                json!({ "command": "sh", "args": ["-c", server.script()] }),
+            )
+        })
+        .collect();
+    std::fs::write(&config, json!({ "mcpServers": servers }).to_string()).unwrap();
+
+    let manager = ProtocolManager::from_config(&config).await.unwrap();
+    std::fs::remove_file(&config).unwrap();
+    manager
+}
+
+#[tokio::test]
+async fn get_protocols_filters_by_client_id() {
+    let manager = manager(
+        &[
+            ("alpha", named_server("alpha")),
+            ("beta", named_server("beta")),
+            ("gamma", named_server("gamma")),
+        ],
+        "get-protocols",
+    )
+    .await;
+
+    let names = |ids: Option<&[String]>| -> Vec<String> {
+        manager
+            .get_protocols(ids)
+            .into_iter()
+            .map(|protocol| protocol.server_info().unwrap().name.clone())
+            .collect()
+    };
+    assert_eq!(names(None), ["alpha", "beta", "gamma"]);
+    assert_eq!(
+        names(Some(&["gamma".to_string(), "alpha".to_string()])),
+        ["alpha", "gamma"]
+    );
+    assert!(names(Some(&["missing".to_string()])).is_empty());
+}
+
+#[tokio::test]
+async fn get_protocol_looks_up_by_client_id() {
+    let manager = manager(
+        &[
+            ("alpha", named_server("alpha")),
+            ("beta", named_server("beta")),
+        ],
+        "get-protocol",
+    )
+    .await;
+
+    let beta = manager.get_protocol("beta").unwrap();
+    assert_eq!(beta.server_info().unwrap().name, "beta");
+    assert!(manager.get_protocol("missing").is_none());
+}
+
+#[tokio::test]
+async fn call_tool_routes_to_the_owning_client() {
+    let manager = manager(
+        &[
+            ("alpha", named_server("alpha")),
+            ("beta", named_server("beta")),
+        ],
+        "call-tool",
+    )
+    .await;
+
+    let answered_by = |response: mcp_client_rs::CallToolResponse| response.into_result().unwrap();
+    assert_eq!(
+        answered_by(
+            manager
+                .call_tool("beta_tool", json!({}), None)
+                .await
+                .unwrap()
+        ),
+        "beta"
+    );
+    assert_eq!(
+        answered_by(
+            manager
+                .call_tool("shared", json!({}), Some("alpha"))
+                .await
+                .unwrap()
+        ),
+        "alpha"
+    );
+
+    for (tool, client_id) in [
+        ("shared", None),
+        ("missing", None),
+        ("beta_tool", Some("alpha")),
+    ] {
+        let result = manager.call_tool(tool, json!({}), client_id).await;
+        assert!(
+            matches!(result, Err(ClientError::ToolError(_))),
+            "{} on {:?}: {:?}",
+            tool,
+            client_id,
+            result
+        );
+    }
+}
+
+#[tokio::test]
+async fn add_protocol_spawns_any_program() {
+    // The server only starts if it gets the token
+    let script = format!(
+        "[ \"$SERVER_TOKEN\" = secret ] || exit 1; {}",
+        named_server("local").script()
+    );
+    let mut manager = ProtocolManager::new();
+    manager
+        .add_protocol(
+            "local",
+            "sh",
+            vec!["-c", &script],
+            HashMap::from([("SERVER_TOKEN".to_string(), "secret".to_string())]),
+            Some(1),
+            Some(vec!["local_tool".to_string()]),
+        )
+        .await
+        .unwrap();
+
+    let tools: Vec<_> = manager
+        .get_tool_structs(None)
+        .into_iter()
+        .map(|tool| tool.name.as_str())
+        .collect();
+    assert_eq!(tools, ["local_tool"]);
+    assert_eq!(
+        manager
+            .call_tool("local_tool", json!({}), None)
+            .await
+            .unwrap()
+            .into_result(),
+        Ok("local".to_string())
+    );
+}
+
+#[tokio::test]
+async fn remove_protocol_renumbers_the_remaining_tools() {
+    let mut manager = manager(
+        &[
+            ("alpha", named_server("alpha")),
+            ("beta", named_server("beta")),
+            ("gamma", named_server("gamma")),
+        ],
+        "remove-protocol",
+    )
+    .await;
+
+    manager.remove_protocol("beta").await.unwrap();
+
+    assert!(manager.get_protocol("beta").is_none());
+    assert!(!manager.client_tools.contains_key("beta"));
+    assert_eq!(
+        manager.get_tools_for_clients(None),
+        "1. alpha_tool: \n2. shared: \n3. gamma_tool: \n4. shared: \n"
+    );
+    assert!(matches!(
+        manager.remove_protocol("beta").await,
+        Err(ClientError::ProtocolError(_))
+    ));
+}
+
+#[tokio::test]
+async fn tools_keep_their_numbers_when_filtered_by_client() {
+    let manager = manager(
+        &[
+            ("alpha", named_server("alpha")),
+            ("beta", named_server("beta")),
+            ("gamma", named_server("gamma")),
+        ],
+        "tool-numbers",
+    )
+    .await;
+
+    assert_eq!(
+        manager.get_tools_for_clients(None),
+        "1. alpha_tool: \n2. shared: \n3. beta_tool: \n4. shared: \n5. gamma_tool: \n6. shared: \n"
+    );
+    assert_eq!(
+        manager.get_tools_for_clients(Some(&["gamma".to_string(), "beta".to_string()])),
+        "3. beta_tool: \n4. shared: \n5. gamma_tool: \n6. shared: \n"
+    );
+}
+
+#[tokio::test]
+async fn clients_removed_from_the_map_are_skipped() {
+    let mut manager = manager(
+        &[
+            ("alpha", named_server("alpha")),
+            ("beta", named_server("beta")),
+        ],
+        "removed-client",
+    )
+    .await;
+
+    manager.clients.remove("alpha");
+
+    let names: Vec<_> = manager
+        .get_protocols(None)
+        .into_iter()
+        .map(|protocol| protocol.server_info().unwrap().name.clone())
+        .collect();
+    assert_eq!(names, ["beta"]);
+}
+
+#[tokio::test]
+async fn tools_removed_from_the_map_are_skipped() {
+    let mut manager = manager(
+        &[
+            ("alpha", named_server("alpha")),
+            ("beta", named_server("beta")),
+        ],
+        "removed-tools",
+    )
+    .await;
+
+    manager.client_tools.remove("alpha");
+
+    assert_eq!(
+        manager.get_tools_for_clients(None),
+        "1. beta_tool: \n2. shared: \n"
+    );
+}
+
+#[tokio::test]
+async fn catalog_lists_tools_in_registration_order() {
+    let mut manager = ProtocolManager::new();
+    for name in ["gamma", "alpha"] {
+        let script = named_server(name).script();
+        manager
+            .add_protocol(name, "sh", vec!["-c", &script], HashMap::new(), None, None)
+            .await
+            .unwrap();
+    }
+
+    let catalog = manager.to_json_catalog();
+    let entries: Vec<_> = catalog
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|entry| format!("{}/{}", entry["client_id"], entry["name"]).replace('"', ""))
+        .collect();
+    assert_eq!(
+        entries,
+        [
+            "gamma/gamma_tool",
+            "gamma/shared",
+            "alpha/alpha_tool",
+            "alpha/shared"
+        ]
+    );
+    assert_eq!(catalog[0]["input_schema"], json!({}));
+}
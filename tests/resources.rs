@@ -0,0 +1,163 @@
+mod common;
+
+use common::FakeServer;
+use mcp_client_rs::{
+    ResourceContents, ResourceTemplate, ResourceTemplatesResponse, ToolResponseContent,
+};
+use serde_json::{json, Value};
+
+#[tokio::test]
+async fn groups_multiple_contents_by_uri() {
+    let client = FakeServer::new()
+        .result(
+            "resources/read",
+            json!({
+                "contents": [
+                    { "uri": "file:///project/src", "mimeType": "text/plain", "text": "main.rs" },
+                    { "uri": "file:///project/src", "mimeType": "text/plain", "text": "lib.rs" },
+                    { "uri": "file:///project/logo.png", "mimeType": "image/png", "blob": "iVBORw0KGgo=" }
+                ]
+            }),
+        )
+        .connect()
+        .await
+        .unwrap();
+
+    let response = client.read_resource("file:///project/src").await.unwrap();
+
+    let src: Vec<&ResourceContents> = response.by_uri("file:///project/src");
+    assert_eq!(src.len(), 2);
+    assert_eq!(src[0].text.as_deref(), Some("main.rs"));
+    assert_eq!(src[1].text.as_deref(), Some("lib.rs"));
+
+    let logo = response.by_uri("file:///project/logo.png");
+    assert_eq!(logo.len(), 1);
+    assert_eq!(logo[0].blob.as_deref(), Some("iVBORw0KGgo="));
+
+    assert!(response.by_uri("file:///missing").is_empty());
+}
+
+#[tokio::test]
+async fn lists_resource_templates() {
+    let client = FakeServer::new()
+        .result(
+            "resources/templates/list",
+            json!({
+                "resourceTemplates": [
+                    { "uriTemplate": "file:///{path}", "name": "Project files", "mimeType": "text/plain" }
+                ]
+            }),
+        )
+        .connect()
+        .await
+        .unwrap();
+
+    let templates: ResourceTemplatesResponse = client.list_resource_templates().await.unwrap();
+    let template: &ResourceTemplate = &templates.resource_templates[0];
+    assert_eq!(template.uri_template, "file:///{path}");
+    assert_eq!(template.description, None);
+}
+
+#[tokio::test]
+async fn read_resources_sends_one_uri_per_request() {
+    let path = std::env::temp_dir().join(format!("mcp-read-{}.jsonl", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    let client = FakeServer::new()
+        .record_to(&path)
+        .result(
+            "resources/read",
+            json!({ "contents": [{ "uri": "file:///a.txt", "text": "a" }] }),
+        )
+        .connect()
+        .await
+        .unwrap();
+
+    let response = client
+        .read_resources(vec![
+            "file:///a.txt".to_string(),
+            "file:///b.txt".to_string(),
+        ])
+        .await
+        .unwrap();
+    assert_eq!(response.contents.len(), 2);
+
+    let recorded = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    let params: Vec<Value> = recorded
+        .lines()
+        .map(|line| serde_json::from_str::<Value>(line).unwrap())
+        .filter(|request| request["method"] == "resources/read")
+        .map(|request| request["params"].clone())
+        .collect();
+    assert_eq!(
+        params,
+        [
+            json!({ "uri": "file:///a.txt" }),
+            json!({ "uri": "file:///b.txt" })
+        ]
+    );
+}
+
+#[tokio::test]
+async fn slow_read_does_not_hold_back_the_others() {
+    let client = FakeServer::new()
+        .delayed(
+            r#"resources/read","params":{"uri":"file:///slow.txt"#,
+            json!({ "contents": [{ "uri": "file:///slow.txt", "text": "slow" }] }),
+            1.0,
+        )
+        .delayed(
+            "resources/read",
+            json!({ "contents": [{ "uri": "file:///fast.txt", "text": "fast" }] }),
+            0.4,
+        )
+        .connect()
+        .await
+        .unwrap();
+
+    let uris = vec![
+        "file:///slow.txt".to_string(),
+        "file:///a.txt".to_string(),
+        "file:///b.txt".to_string(),
+    ];
+    let started = std::time::Instant::now();
+    let results = client
+        .read_resources_concurrent(uris.clone(), Some(2))
+        .await;
+    let elapsed = started.elapsed();
+
+    // In-order buffering would only start the third read once the slow one finished
+    assert!(
+        elapsed < std::time::Duration::from_millis(1250),
+        "took {:?}",
+        elapsed
+    );
+    let returned: Vec<&String> = results.iter().map(|(uri, _)| uri).collect();
+    assert_eq!(returned, uris.iter().collect::<Vec<_>>());
+    let texts: Vec<Option<String>> = results
+        .into_iter()
+        .map(|(_, result)| result.unwrap().contents[0].text.clone())
+        .collect();
+    assert_eq!(
+        texts,
+        [Some("slow"), Some("fast"), Some("fast")].map(|text| text.map(String::from))
+    );
+}
+
+#[test]
+fn embeds_resource_contents_in_tool_content() {
+    let content = ToolResponseContent::resource(ResourceContents {
+        uri: "file:///a.txt".parse().unwrap(),
+        mime_type: Some("text/plain".to_string()),
+        text: Some("a".to_string()),
+        blob: None,
+    });
+    assert_eq!(
+        serde_json::to_value(&content).unwrap(),
+        json!({
+            "type": "resource",
+            "resource": { "uri": "file:///a.txt", "mimeType": "text/plain", "text": "a" }
+        })
+    );
+}
@@ -0,0 +1,173 @@
+mod common;
+
+use common::FakeServer;
+use mcp_client_rs::ClientError;
+use serde_json::json;
+
+#[tokio::test]
+async fn unwraps_array_wrapped_single_response() {
+    let client = FakeServer::new()
+        .raw(
+            "tools/list",
+            r#"[{"jsonrpc":"2.0","id":{id},"result":{"tools":[{"name":"echo","description":"Echo input","inputSchema":{"type":"object"}}]}}]"#,
+        )
+        .connect()
+        .await
+        .unwrap();
+
+    let tools = client.list_tools().await.unwrap();
+    assert_eq!(tools.tools.len(), 1);
+    assert_eq!(tools.tools[0].name, "echo");
+}
+
+#[tokio::test]
+async fn tools_carry_their_input_schema() {
+    let client = FakeServer::new()
+        .raw(
+            "tools/list",
+            r#"{"jsonrpc":"2.0","id":{id},"result":{"tools":[{"name":"search","description":"Search","inputSchema":{"type":"object","properties":{"query":{"type":"string"},"limit":{"type":"integer"}},"required":["query"]}},{"name":"now","description":"Time","inputSchema":{"type":"object"}}]}}"#,
+        )
+        .connect()
+        .await
+        .unwrap();
+
+    let tools = client.list_tools().await.unwrap().tools;
+    assert_eq!(
+        tools[0].input_schema["properties"]["limit"]["type"],
+        "integer"
+    );
+    assert_eq!(tools[0].required_params(), ["query"]);
+    assert!(tools[1].required_params().is_empty());
+}
+
+#[tokio::test]
+async fn picks_matching_response_out_of_batch() {
+    let client = FakeServer::new()
+        .raw(
+            "tools/list",
+            r#"[{"jsonrpc":"2.0","id":999,"result":{"tools":[]}},{"jsonrpc":"2.0","id":{id},"result":{"tools":[{"name":"echo","description":"Echo input","inputSchema":{}}]}}]"#,
+        )
+        .connect()
+        .await
+        .unwrap();
+
+    let tools = client.list_tools().await.unwrap();
+    assert_eq!(tools.tools.len(), 1);
+}
+
+#[tokio::test]
+async fn null_id_error_becomes_protocol_error() {
+    let client = FakeServer::new()
+        .raw(
+            "tools/list",
+            r#"{"jsonrpc":"2.0","id":null,"error":{"code":-32700,"message":"Parse error"}}"#,
+        )
+        .connect()
+        .await
+        .unwrap();
+
+    match client.list_tools().await {
+        Err(ClientError::ProtocolError(message)) => assert!(message.contains("Parse error")),
+        other => panic!("expected a protocol error, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn error_response_keeps_code_and_data() {
+    let client = FakeServer::new()
+        .raw(
+            "tools/call",
+            r#"{"jsonrpc":"2.0","id":{id},"error":{"code":-32602,"message":"Unknown tool: nope","data":{"tool":"nope"}}}"#,
+        )
+        .connect()
+        .await
+        .unwrap();
+
+    match client.call_tool("nope", json!({})).await {
+        Err(ClientError::Rpc {
+            code,
+            message,
+            data,
+        }) => {
+            assert_eq!(code, -32602);
+            assert_eq!(message, "Unknown tool: nope");
+            assert_eq!(data, Some(json!({ "tool": "nope" })));
+        }
+        other => panic!("expected an RPC error, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn parses_tool_result_content_array() {
+    let client = FakeServer::new()
+        .raw(
+            "tools/call",
+            r#"{"jsonrpc":"2.0","id":{id},"result":{"content":[{"type":"text","text":"no such repo"},{"type":"image","data":"aGk=","mimeType":"image/png"},{"type":"text","text":"check the owner"}],"isError":true}}"#,
+        )
+        .connect()
+        .await
+        .unwrap();
+
+    let response = client
+        .call_tool("search", serde_json::json!({}))
+        .await
+        .unwrap();
+    assert!(response.is_error);
+    assert_eq!(response.content.len(), 3);
+    assert_eq!(response.content[1].to_string(), "[image: image/png]");
+    assert_eq!(
+        response.into_result(),
+        Err("no such repo\ncheck the owner".to_string())
+    );
+}
+
+#[tokio::test]
+async fn lines_that_are_not_json_are_skipped() {
+    let client = FakeServer::new()
+        .raw(
+            "tools/list",
+            "\nStarting up...\n{\"jsonrpc\":\"2.0\",\"id\":\n{\"jsonrpc\":\"2.0\",\"id\":{id},\"result\":{\"tools\":[]}}",
+        )
+        .connect()
+        .await
+        .unwrap();
+
+    let (tools, pinged) = tokio::join!(client.list_tools(), client.ping());
+    assert!(tools.unwrap().tools.is_empty());
+    // Neither request is failed by the garbage; each gets its own answer
+    assert!(matches!(pinged, Err(ClientError::Rpc { code: -32601, .. })));
+}
+
+#[tokio::test]
+async fn null_id_error_is_dropped_when_several_requests_are_in_flight() {
+    let client = FakeServer::new()
+        .raw(
+            "tools/list",
+            "{\"jsonrpc\":\"2.0\",\"id\":null,\"error\":{\"code\":-32700,\"message\":\"Parse error\"}}\n\
+             {\"jsonrpc\":\"2.0\",\"id\":{id},\"result\":{\"tools\":[]}}",
+        )
+        .result("ping", json!({}))
+        .connect()
+        .await
+        .unwrap();
+
+    // Either request could be the one the server failed to parse, so neither gets the error
+    let (tools, pinged) = tokio::join!(client.list_tools(), client.ping());
+    assert!(tools.unwrap().tools.is_empty());
+    pinged.unwrap();
+}
+
+#[tokio::test]
+async fn responses_without_an_id_are_dropped() {
+    let client = FakeServer::new()
+        .raw(
+            "tools/list",
+            "{\"jsonrpc\":\"2.0\",\"result\":{\"tools\":[{\"name\":\"stray\",\"description\":\"\",\"inputSchema\":{}}]}}\n\
+             {\"jsonrpc\":\"2.0\",\"id\":{id},\"result\":{\"tools\":[]}}",
+        )
+        .connect()
+        .await
+        .unwrap();
+
+    assert!(client.list_tools().await.unwrap().tools.is_empty());
+}
@@ -0,0 +1,121 @@
+mod common;
+
+use common::FakeServer;
+use mcp_client_rs::{ClientError, ConnectionState, ExponentialBackoff, Protocol};
+use serde_json::json;
+use std::path::PathBuf;
+use std::time::Duration;
+
+fn backoff(max_retries: u32) -> ExponentialBackoff {
+    ExponentialBackoff {
+        initial_delay: Duration::from_millis(10),
+        max_retries,
+        ..Default::default()
+    }
+}
+
+// Runs `first` the first time it is started and `then` every time after
+fn restarted(label: &str, first: &str, then: &str) -> (String, PathBuf) {
+    let marker = std::env::temp_dir().join(format!("mcp-restart-{}-{}", label, std::process::id()));
+    let _ = std::fs::remove_file(&marker);
+    let script = format!(
+        "if [ -e '{marker}' ]; then\n{then}\nelse\ntouch '{marker}'\n{first}\nfi",
+        marker = marker.display(),
+    );
+    (script, marker)
+}
+
+async fn connect(script: &str, max_retries: u32) -> Protocol {
+    Protocol::builder()
+        .program("sh")
+        .args(vec!["-c", script])
+        .restart_policy(backoff(max_retries))
+        .build()
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn restarts_the_server_and_resends_requests() {
+    let path = std::env::temp_dir().join(format!("mcp-restart-{}.jsonl", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let (script, marker) = restarted(
+        "resend",
+        &FakeServer::new().exit_on("tools/list").script(),
+        &FakeServer::new()
+            .record_to(&path)
+            .result("tools/list", json!({ "tools": [] }))
+            .script(),
+    );
+    let client = connect(&script, 3).await;
+    let states = client.watch_connection_state();
+
+    client.list_tools().await.unwrap();
+
+    assert!(states.has_changed().unwrap());
+    assert_eq!(client.connection_state(), ConnectionState::Connected);
+    let methods: Vec<_> = std::fs::read_to_string(&path)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap()["method"].clone())
+        .collect();
+    assert_eq!(
+        methods,
+        ["initialize", "notifications/initialized", "tools/list"]
+    );
+    std::fs::remove_file(&path).unwrap();
+    std::fs::remove_file(&marker).unwrap();
+}
+
+#[tokio::test]
+async fn tool_calls_cut_off_by_a_restart_are_not_resent() {
+    let healthy = FakeServer::new().result(
+        "tools/call",
+        json!({ "content": [{ "type": "text", "text": "done" }] }),
+    );
+    let (script, marker) = restarted(
+        "tool-call",
+        &FakeServer::new().exit_on("tools/call").script(),
+        &healthy.script(),
+    );
+    let client = connect(&script, 3).await;
+
+    let cut_off = client.call_tool("write", json!({})).await;
+    assert!(
+        matches!(cut_off, Err(ClientError::Disconnected)),
+        "{:?}",
+        cut_off
+    );
+    let retried = client.call_tool("write", json!({})).await.unwrap();
+    assert_eq!(retried.into_result(), Ok("done".to_string()));
+    std::fs::remove_file(&marker).unwrap();
+}
+
+#[tokio::test]
+async fn closes_the_connection_when_restarts_keep_failing() {
+    let (script, marker) = restarted(
+        "give-up",
+        &FakeServer::new().exit_on("ping").script(),
+        "exit 1",
+    );
+    let client = connect(&script, 2).await;
+
+    let result = client.ping().await;
+    assert!(
+        matches!(result, Err(ClientError::ServerClosed { .. })),
+        "{:?}",
+        result
+    );
+    assert_eq!(client.connection_state(), ConnectionState::Closed);
+    std::fs::remove_file(&marker).unwrap();
+}
+
+#[tokio::test]
+async fn only_spawned_servers_can_be_restarted() {
+    let result = Protocol::builder()
+        .transport(common::mock_transport::MockTransport::new())
+        .restart_policy(backoff(1))
+        .build()
+        .await;
+    assert!(matches!(result, Err(ClientError::InitializationFailed(_))));
+}
@@ -0,0 +1,40 @@
+use mcp_client_rs::{ClientError, ExponentialBackoff, RetryPolicy};
+use std::time::Duration;
+
+#[test]
+fn backoff_grows_up_to_the_max_delay() {
+    let backoff = ExponentialBackoff {
+        max_retries: u32::MAX,
+        ..Default::default()
+    };
+    let delay = |attempt| backoff.next_delay(attempt, &ClientError::Timeout);
+
+    assert_eq!(delay(0), None);
+    assert_eq!(delay(1), Some(Duration::from_millis(100)));
+    assert_eq!(delay(2), Some(Duration::from_millis(200)));
+    // Far past the point where the unclamped delay overflows a Duration
+    for attempt in [68, 1_000, u32::MAX] {
+        assert_eq!(delay(attempt), Some(backoff.max_delay));
+    }
+}
+
+#[test]
+fn backoff_with_an_invalid_multiplier_stays_in_range() {
+    for multiplier in [-2.0, f64::NAN, f64::INFINITY] {
+        let backoff = ExponentialBackoff {
+            multiplier,
+            max_retries: 10,
+            ..Default::default()
+        };
+        for attempt in 1..=10 {
+            let delay = backoff.next_delay(attempt, &ClientError::Timeout).unwrap();
+            assert!(delay <= backoff.max_delay, "{} {:?}", multiplier, delay);
+        }
+    }
+}
+
+#[test]
+#[should_panic(expected = "multiplier")]
+fn backoff_multiplier_below_one_is_rejected() {
+    ExponentialBackoff::new(Duration::from_millis(100), Duration::from_secs(1), 0.5, 3);
+}
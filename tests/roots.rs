@@ -0,0 +1,86 @@
+mod common;
+
+use common::FakeServer;
+use mcp_client_rs::{ClientCapabilities, Protocol, Root, RootsCapability};
+use serde_json::{json, Value};
+use std::time::Duration;
+
+#[tokio::test]
+async fn answers_roots_list_from_the_server() {
+    let path = std::env::temp_dir().join(format!("mcp-roots-{}.jsonl", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    // The server asks for roots just before answering tools/list
+    let client = FakeServer::new()
+        .record_to(&path)
+        .raw(
+            "tools/list",
+            "{\"jsonrpc\":\"2.0\",\"id\":\"roots-1\",\"method\":\"roots/list\"}\n\
+             {\"jsonrpc\":\"2.0\",\"id\":{id},\"result\":{\"tools\":[]}}",
+        )
+        .connect()
+        .await
+        .unwrap();
+    client
+        .set_roots(vec![Root {
+            uri: "file:///home/user/project".to_string(),
+            name: Some("project".to_string()),
+        }])
+        .await
+        .unwrap();
+
+    client.list_tools().await.unwrap();
+
+    let mut answer = None;
+    for _ in 0..50 {
+        let recorded = std::fs::read_to_string(&path).unwrap_or_default();
+        answer = recorded
+            .lines()
+            .map(|line| serde_json::from_str::<Value>(line).unwrap())
+            .find(|message| message["id"] == "roots-1");
+        if answer.is_some() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(
+        answer.expect("roots/list was not answered")["result"],
+        json!({ "roots": [{ "uri": "file:///home/user/project", "name": "project" }] })
+    );
+}
+
+#[tokio::test]
+async fn changing_roots_notifies_the_server() {
+    let path = std::env::temp_dir().join(format!("mcp-roots-changed-{}.jsonl", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    let script = FakeServer::new()
+        .record_to(&path)
+        .result("ping", json!({}))
+        .script();
+    let client = Protocol::builder()
+        .program("sh")
+        .args(vec!["-c", &script])
+        .capabilities(ClientCapabilities {
+            roots: Some(RootsCapability { list_changed: true }),
+            ..Default::default()
+        })
+        .build()
+        .await
+        .unwrap();
+    client.set_roots(vec![]).await.unwrap();
+    // Requests are handled in order, so the notification is recorded once the ping is answered
+    client.ping().await.unwrap();
+
+    let recorded = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert!(recorded
+        .lines()
+        .map(|line| serde_json::from_str::<Value>(line).unwrap())
+        .any(
+            |message| message["method"] == "notifications/roots/list_changed"
+                && message.get("id").is_none()
+        ));
+}
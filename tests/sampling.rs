@@ -0,0 +1,181 @@
+mod common;
+
+use common::{initialize_result, FakeServer};
+use mcp_client_rs::Protocol;
+use mcp_client_rs::{CreateMessageResult, ResourceLink, Role, StopReason, ToolResponseContent};
+use serde_json::{json, Value};
+use std::path::Path;
+use std::time::Duration;
+
+// The server asks the client to sample just before answering tools/list
+const SAMPLING_REQUEST: &str = "{\"jsonrpc\":\"2.0\",\"id\":\"sample-1\",\"method\":\"sampling/createMessage\",\
+    \"params\":{\"messages\":[{\"role\":\"user\",\"content\":{\"type\":\"text\",\"text\":\"Hi\"}}],\"maxTokens\":100}}\n\
+    {\"jsonrpc\":\"2.0\",\"id\":{id},\"result\":{\"tools\":[]}}";
+
+// Wait for the client's answer to the sampling request `id` to be recorded
+async fn answer(path: &Path, id: &str) -> Value {
+    let mut answer = None;
+    for _ in 0..50 {
+        let recorded = std::fs::read_to_string(path).unwrap_or_default();
+        answer = recorded
+            .lines()
+            .map(|line| serde_json::from_str::<Value>(line).unwrap())
+            .find(|message| message["id"] == id);
+        if answer.is_some() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    std::fs::remove_file(path).unwrap();
+    answer.expect("sampling/createMessage was not answered")
+}
+
+#[tokio::test]
+async fn answers_sampling_requests_with_the_handler() {
+    let path = std::env::temp_dir().join(format!("mcp-sampling-{}.jsonl", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    let client = FakeServer::new()
+        .record_to(&path)
+        .raw("tools/list", SAMPLING_REQUEST)
+        .connect()
+        .await
+        .unwrap();
+    client.on_sampling_request(|params| async move {
+        let prompt = match &params.messages[0].content {
+            ToolResponseContent::Text { text, .. } => text.clone(),
+            _ => return Err("Only text is supported".to_string()),
+        };
+        Ok(CreateMessageResult {
+            role: Role::Assistant,
+            content: ToolResponseContent::Text {
+                text: format!("{} back, in at most {} tokens", prompt, params.max_tokens),
+                annotations: None,
+            },
+            model: "test-model".to_string(),
+            stop_reason: Some(StopReason::EndTurn),
+        })
+    });
+
+    client.list_tools().await.unwrap();
+
+    let answer = answer(&path, "sample-1").await;
+    assert_eq!(
+        answer["result"]["content"]["text"],
+        "Hi back, in at most 100 tokens"
+    );
+    assert_eq!(answer["result"]["model"], "test-model");
+    assert_eq!(answer["result"]["role"], "assistant");
+}
+
+#[tokio::test]
+async fn rejects_sampling_requests_without_a_handler() {
+    let path = std::env::temp_dir().join(format!(
+        "mcp-sampling-unsupported-{}.jsonl",
+        std::process::id()
+    ));
+    let _ = std::fs::remove_file(&path);
+
+    let client = FakeServer::new()
+        .record_to(&path)
+        .raw("tools/list", SAMPLING_REQUEST)
+        .connect()
+        .await
+        .unwrap();
+
+    client.list_tools().await.unwrap();
+
+    assert_eq!(answer(&path, "sample-1").await["error"]["code"], -32601);
+}
+
+#[tokio::test]
+async fn refuses_sampled_content_of_a_disallowed_type() {
+    let path =
+        std::env::temp_dir().join(format!("mcp-sampling-content-{}.jsonl", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    let client = FakeServer::new()
+        .record_to(&path)
+        .raw("tools/list", SAMPLING_REQUEST)
+        .connect()
+        .await
+        .unwrap();
+    client.on_sampling_request(|_| async {
+        Ok(CreateMessageResult {
+            role: Role::Assistant,
+            content: ToolResponseContent::ResourceLink(ResourceLink {
+                uri: "file:///answer.txt".parse().unwrap(),
+                name: "answer".to_string(),
+                description: None,
+                mime_type: None,
+                annotations: None,
+            }),
+            model: "test-model".to_string(),
+            stop_reason: None,
+        })
+    });
+
+    client.list_tools().await.unwrap();
+
+    let answer = answer(&path, "sample-1").await;
+    assert_eq!(answer["error"]["code"], -32603);
+    assert_eq!(
+        answer["error"]["message"],
+        "Sampling produced resource_link content, only text and image are allowed"
+    );
+}
+
+#[tokio::test]
+async fn declines_sampling_requests_over_the_token_limit() {
+    let path =
+        std::env::temp_dir().join(format!("mcp-sampling-limit-{}.jsonl", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    let mut initialize = initialize_result();
+    initialize["capabilities"]["sampling"] = json!({ "max_tokens": 50, "supported_methods": [] });
+    let client = FakeServer::new()
+        .record_to(&path)
+        .initialize(initialize)
+        .raw("tools/list", SAMPLING_REQUEST)
+        .connect()
+        .await
+        .unwrap();
+    client.on_sampling_request(|_| async { Err("the handler should not be called".to_string()) });
+
+    client.list_tools().await.unwrap();
+
+    let answer = answer(&path, "sample-1").await;
+    assert_eq!(answer["error"]["code"], -1);
+    assert_eq!(
+        answer["error"]["message"],
+        "Requested 100 tokens, more than the limit of 50"
+    );
+}
+
+#[tokio::test]
+async fn refuses_server_requests_over_the_limit() {
+    let path = std::env::temp_dir().join(format!("mcp-sampling-busy-{}.jsonl", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    let (first, result) = SAMPLING_REQUEST.split_once('\n').unwrap();
+    let second = first.replace("sample-1", "sample-2");
+    let script = FakeServer::new()
+        .record_to(&path)
+        .raw("tools/list", &format!("{}\n{}\n{}", first, second, result))
+        .script();
+    let client = Protocol::builder()
+        .program("sh")
+        .args(vec!["-c", &script])
+        .max_pending_server_requests(1)
+        .build()
+        .await
+        .unwrap();
+    // The first request takes the only slot and is never answered
+    client.on_sampling_request(|_| std::future::pending());
+
+    client.list_tools().await.unwrap();
+
+    let answer = answer(&path, "sample-2").await;
+    assert_eq!(answer["error"]["code"], -32000);
+    assert_eq!(answer["error"]["message"], "Client is busy");
+}
@@ -0,0 +1,46 @@
+mod common;
+
+use common::FakeServer;
+use mcp_client_rs::{ClientError, Protocol, DEFAULT_PROTOCOL_VERSION};
+use serde_json::json;
+use std::collections::HashMap;
+
+#[tokio::test]
+async fn broken_pipe_closes_the_session() {
+    let client = FakeServer::new()
+        .result("ping", json!({}))
+        .close_stdin_after("ping")
+        .connect()
+        .await
+        .unwrap();
+    client.ping().await.unwrap();
+
+    assert!(matches!(
+        client.list_tools().await,
+        Err(ClientError::ServerClosed { .. })
+    ));
+    // Later requests fail the same way without touching the pipe again
+    assert!(matches!(
+        client.ping().await,
+        Err(ClientError::ServerClosed { .. })
+    ));
+}
+
+#[tokio::test]
+async fn startup_failure_reports_server_stderr() {
+    let result = Protocol::new(
+        DEFAULT_PROTOCOL_VERSION,
+        "sh",
+        vec!["-c", "echo 'error: GITHUB_TOKEN is not set' >&2; exit 1"],
+        HashMap::new(),
+    )
+    .await;
+
+    match result {
+        Err(ClientError::InitializationFailed(message)) => {
+            assert!(message.contains("GITHUB_TOKEN is not set"), "{}", message)
+        }
+        Err(other) => panic!("expected an initialization failure, got {:?}", other),
+        Ok(_) => panic!("expected an initialization failure"),
+    }
+}
@@ -0,0 +1,86 @@
+mod common;
+
+use common::FakeServer;
+use mcp_client_rs::{ClientError, LogLevel, LogSource, Protocol, DEFAULT_PROTOCOL_VERSION};
+use serde_json::json;
+use std::{collections::HashMap, time::Duration};
+
+#[tokio::test]
+async fn stderr_lines_are_kept_in_recent_logs() {
+    let script = format!(
+        "echo 'starting up' >&2; echo 'ready' >&2; {}",
+        FakeServer::new().script()
+    );
+    let client = Protocol::new(
+        DEFAULT_PROTOCOL_VERSION,
+        "sh",
+        vec!["-c", &script],
+        HashMap::new(),
+    )
+    .await
+    .unwrap();
+
+    // Stderr is read in the background
+    for _ in 0..50 {
+        if client.recent_logs(LogLevel::Debug, 10).len() == 2 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+
+    let logs = client.recent_logs(LogLevel::Debug, 10);
+    let messages: Vec<_> = logs.iter().map(|l| l.message.as_str()).collect();
+    assert_eq!(messages, ["starting up", "ready"]);
+    assert!(logs.iter().all(|l| l.source == LogSource::Stderr));
+
+    assert_eq!(client.recent_logs(LogLevel::Debug, 1)[0].message, "ready");
+    assert!(client.recent_logs(LogLevel::Error, 10).is_empty());
+}
+
+#[tokio::test]
+async fn set_log_level_checks_advertised_levels() {
+    let client = FakeServer::new()
+        .result("logging/setLevel", json!({}))
+        .connect()
+        .await
+        .unwrap();
+
+    client.set_log_level("warning").await.unwrap();
+    match client.set_log_level("verbose").await {
+        Err(ClientError::CapabilityError(message)) => {
+            assert!(message.contains("debug, info, warning, error"))
+        }
+        other => panic!("expected a capability error, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn log_notifications_do_not_push_out_stderr() {
+    // One stderr line, then more log notifications than are kept
+    let notification = r#"{"jsonrpc":"2.0","method":"notifications/message","params":{"level":"info","data":"chatter"}}"#;
+    let script = format!(
+        "echo 'fatal: out of disk' >&2; sleep 0.2; i=0; while [ $i -lt 1100 ]; do printf '%s\\n' '{}'; i=$((i+1)); done; {}",
+        notification,
+        FakeServer::new().exit_on("tools/list").script()
+    );
+    let client = Protocol::new(
+        DEFAULT_PROTOCOL_VERSION,
+        "sh",
+        vec!["-c", &script],
+        HashMap::new(),
+    )
+    .await
+    .unwrap();
+
+    let logs = client.recent_logs(LogLevel::Debug, 2000);
+    assert_eq!(logs.len(), 1001);
+    assert_eq!(logs[0].message, "fatal: out of disk");
+    assert!(logs[1..]
+        .iter()
+        .all(|l| l.source == LogSource::Notification && l.message == "chatter"));
+
+    match client.list_tools().await {
+        Err(ClientError::ServerClosed { stderr }) => assert_eq!(stderr, ["fatal: out of disk"]),
+        other => panic!("expected the server to close, got {:?}", other),
+    }
+}
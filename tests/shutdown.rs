@@ -0,0 +1,40 @@
+mod common;
+
+use common::FakeServer;
+use mcp_client_rs::ClientError;
+use serde_json::{json, Value};
+use std::time::{Duration, Instant};
+
+#[tokio::test]
+async fn shutdown_unsubscribes_and_stops_the_server() {
+    let path = std::env::temp_dir().join(format!("mcp-shutdown-{}.jsonl", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    let client = FakeServer::new()
+        .record_to(&path)
+        .result("resources/subscribe", json!({}))
+        .result("resources/unsubscribe", json!({}))
+        .connect()
+        .await
+        .unwrap();
+    client.subscribe_resource("file:///a.txt").await.unwrap();
+
+    // The fake server exits as soon as its stdin closes, well within the grace period
+    let started = Instant::now();
+    client.shutdown().await.unwrap();
+    assert!(started.elapsed() < Duration::from_secs(1));
+    assert!(client.active_subscriptions().is_empty());
+    assert!(matches!(
+        client.ping().await,
+        Err(ClientError::ServerClosed { .. })
+    ));
+
+    let recorded = std::fs::read_to_string(&path).unwrap();
+    let _ = std::fs::remove_file(&path);
+    let unsubscribe: Value = recorded
+        .lines()
+        .map(|line| serde_json::from_str::<Value>(line).unwrap())
+        .find(|request| request["method"] == "resources/unsubscribe")
+        .expect("no resources/unsubscribe request sent");
+    assert_eq!(unsubscribe["params"]["uri"], "file:///a.txt");
+}
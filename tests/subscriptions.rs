@@ -0,0 +1,42 @@
+mod common;
+
+use common::{initialize_result, FakeServer};
+use mcp_client_rs::ClientError;
+use serde_json::json;
+
+#[tokio::test]
+async fn subscriptions_are_tracked() {
+    let client = FakeServer::new()
+        .result("resources/subscribe", json!({}))
+        .result("resources/unsubscribe", json!({}))
+        .connect()
+        .await
+        .unwrap();
+
+    client.subscribe_resource("file:///b.txt").await.unwrap();
+    client.subscribe_resource("file:///a.txt").await.unwrap();
+    assert_eq!(
+        client.active_subscriptions(),
+        ["file:///a.txt", "file:///b.txt"]
+    );
+
+    client.unsubscribe_resource("file:///a.txt").await.unwrap();
+    assert_eq!(client.active_subscriptions(), ["file:///b.txt"]);
+
+    assert!(matches!(
+        client.unsubscribe_resource("file:///a.txt").await,
+        Err(ClientError::ResourceError(_))
+    ));
+}
+
+#[tokio::test]
+async fn subscribe_requires_supports_subscribe() {
+    let mut init = initialize_result();
+    init["capabilities"]["resources"]["supports_subscribe"] = json!(false);
+    let client = FakeServer::new().initialize(init).connect().await.unwrap();
+
+    assert!(matches!(
+        client.subscribe_resource("file:///a.txt").await,
+        Err(ClientError::CapabilityError(_))
+    ));
+}
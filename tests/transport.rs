@@ -0,0 +1,69 @@
+mod common;
+
+use common::initialize_result;
+use futures::future::BoxFuture;
+use mcp_client_rs::{ClientError, Protocol, Transport};
+use serde_json::{json, Value};
+use tokio::sync::{mpsc, Mutex};
+
+// Answers requests in memory, as a server on another transport would
+struct InMemoryTransport {
+    responses: mpsc::UnboundedSender<String>,
+    incoming: Mutex<mpsc::UnboundedReceiver<String>>,
+}
+
+impl InMemoryTransport {
+    fn new() -> Self {
+        let (responses, incoming) = mpsc::unbounded_channel();
+        Self {
+            responses,
+            incoming: Mutex::new(incoming),
+        }
+    }
+}
+
+impl Transport for InMemoryTransport {
+    fn send<'a>(&'a self, message: &'a str) -> BoxFuture<'a, Result<(), ClientError>> {
+        Box::pin(async move {
+            let request: Value = serde_json::from_str(message)?;
+            let result = match request["method"].as_str() {
+                Some("initialize") => initialize_result(),
+                Some("tools/list") => json!({ "tools": [
+                    { "name": "echo", "description": "Echo input", "inputSchema": {} }
+                ] }),
+                _ => return Ok(()),
+            };
+            let response = json!({ "jsonrpc": "2.0", "id": request["id"], "result": result });
+            self.responses
+                .send(response.to_string())
+                .map_err(ClientError::transport)
+        })
+    }
+
+    fn recv(&self) -> BoxFuture<'_, Result<Option<String>, ClientError>> {
+        Box::pin(async move { Ok(self.incoming.lock().await.recv().await) })
+    }
+
+    fn close(&self) -> BoxFuture<'_, Result<(), ClientError>> {
+        Box::pin(async { Ok(()) })
+    }
+}
+
+#[tokio::test]
+async fn protocol_runs_over_a_custom_transport() {
+    let client = Protocol::builder()
+        .transport(InMemoryTransport::new())
+        .build()
+        .await
+        .unwrap();
+
+    assert_eq!(client.server_info().unwrap().name, "fake-server");
+    let tools = client.list_tools().await.unwrap();
+    assert_eq!(tools.tools[0].name, "echo");
+
+    // Raw writes aren't part of the trait's required surface
+    assert!(matches!(
+        client.write_raw(b"{}\n").await,
+        Err(ClientError::Transport(_))
+    ));
+}
@@ -0,0 +1,147 @@
+mod common;
+
+use common::FakeServer;
+use mcp_client_rs::{ClientCapabilities, Direction, Protocol, RootsCapability};
+use serde_json::{json, Value};
+use std::sync::{Arc, Mutex};
+
+#[tokio::test]
+async fn initialize_frame_matches_golden() {
+    let script = FakeServer::new().script();
+    let sent = Arc::new(Mutex::new(vec![]));
+    let recorded = sent.clone();
+    let client = Protocol::builder()
+        .program("sh")
+        .args(vec!["-c", &script])
+        .on_raw_message(move |direction, message| {
+            if direction == Direction::Outbound {
+                recorded.lock().unwrap().push(message.to_string());
+            }
+        })
+        .build()
+        .await
+        .unwrap();
+    drop(client);
+
+    let sent = sent.lock().unwrap();
+    let frame = sent.first().expect("no initialize frame sent");
+    let sent: Value = serde_json::from_str(frame).unwrap();
+    let golden: Value = serde_json::from_str(include_str!("golden/initialize.json")).unwrap();
+    assert_eq!(sent, golden);
+}
+
+#[tokio::test]
+async fn initialized_notification_follows_initialize() {
+    let path = std::env::temp_dir().join(format!("mcp-initialized-{}.jsonl", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    let client = FakeServer::new()
+        .record_to(&path)
+        .result("tools/list", json!({ "tools": [] }))
+        .connect()
+        .await
+        .unwrap();
+    client.list_tools().await.unwrap();
+
+    let recorded = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    let methods: Vec<Value> = recorded
+        .lines()
+        .map(|line| serde_json::from_str::<Value>(line).unwrap())
+        .collect();
+    assert_eq!(
+        methods[1],
+        json!({ "jsonrpc": "2.0", "method": "notifications/initialized", "params": {} })
+    );
+    assert_eq!(methods[2]["method"], "tools/list");
+}
+
+#[tokio::test]
+async fn initialize_sends_configured_client_info_and_capabilities() {
+    let path = std::env::temp_dir().join(format!("mcp-client-info-{}.jsonl", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    let script = FakeServer::new().record_to(&path).script();
+    let client = Protocol::builder()
+        .program("sh")
+        .args(vec!["-c", &script])
+        .client_info("my-agent", "2.3.0")
+        .capabilities(ClientCapabilities {
+            roots: Some(RootsCapability { list_changed: true }),
+            ..Default::default()
+        })
+        .build()
+        .await
+        .unwrap();
+    drop(client);
+
+    let recorded = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    let sent: Value = serde_json::from_str(recorded.lines().next().unwrap()).unwrap();
+    assert_eq!(
+        sent["params"]["clientInfo"],
+        json!({ "name": "my-agent", "version": "2.3.0" })
+    );
+    assert_eq!(
+        sent["params"]["capabilities"],
+        json!({ "roots": { "listChanged": true } })
+    );
+}
+
+#[tokio::test]
+async fn listed_tools_serialize_with_spec_field_names() {
+    let tool = json!({
+        "name": "echo",
+        "description": "Echo input",
+        "inputSchema": { "type": "object" }
+    });
+    let client = FakeServer::new()
+        .result("tools/list", json!({ "tools": [tool.clone()] }))
+        .connect()
+        .await
+        .unwrap();
+
+    let tools = client.list_tools().await.unwrap();
+    assert_eq!(serde_json::to_value(&tools.tools[0]).unwrap(), tool);
+}
+
+#[tokio::test]
+async fn raw_message_hook_sees_both_directions() {
+    let script = FakeServer::new().result("ping", json!({})).script();
+    let seen = Arc::new(Mutex::new(vec![]));
+    let recorded = seen.clone();
+    let client = Protocol::builder()
+        .program("sh")
+        .args(vec!["-c", &script])
+        .on_raw_message(move |direction, message| {
+            let message: Value = serde_json::from_str(message).unwrap();
+            recorded.lock().unwrap().push((direction, message));
+        })
+        .build()
+        .await
+        .unwrap();
+    client.ping().await.unwrap();
+
+    let seen = seen.lock().unwrap();
+    let summary: Vec<_> = seen
+        .iter()
+        .map(|(direction, message)| {
+            let kind = match (&message["method"], &message["result"]) {
+                (Value::String(method), _) => method.clone(),
+                (_, Value::Object(_)) => "result".to_string(),
+                _ => panic!("unexpected message {}", message),
+            };
+            (*direction, kind)
+        })
+        .collect();
+    assert_eq!(
+        summary,
+        [
+            (Direction::Outbound, "initialize".to_string()),
+            (Direction::Inbound, "result".to_string()),
+            (Direction::Outbound, "notifications/initialized".to_string()),
+            (Direction::Outbound, "ping".to_string()),
+            (Direction::Inbound, "result".to_string()),
+        ]
+    );
+}